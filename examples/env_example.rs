@@ -0,0 +1,16 @@
+use rs_envflag::Flag;
+use rs_envflag_macros::*;
+
+/// the port to listen on
+#[envflag(default = 8080)]
+const PORT: i64;
+
+/// the password of the database
+#[envflag(secret)]
+const DB_PASSWORD: Option<String>;
+
+const FLAGS: &[&dyn Flag] = &[&PORT, &DB_PASSWORD];
+
+fn main() {
+    print!("{}", rs_envflag::generate::env_example(FLAGS));
+}
@@ -5,8 +5,10 @@ use std::collections::VecDeque;
 
 #[proc_macro_attribute]
 pub fn envflag(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let flag = FlagContext::new(attr.into_iter().collect(), item.into_iter().collect());
-    flag.into()
+    match FlagContext::new(attr.into_iter().collect(), item.into_iter().collect()) {
+        Ok(flag) => flag.into(),
+        Err(e) => e,
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -16,30 +18,206 @@ struct FlagContext {
     base_type: VecDeque<TokenTree>,
     has_option: bool,
     default: Vec<TokenTree>,
+    profiles: Vec<(String, Vec<TokenTree>)>,
+    default_str: Option<Literal>,
+    default_fn: Option<Vec<TokenTree>>,
+    depends_on: Vec<Vec<TokenTree>>,
+    secret: bool,
+    allow_file: bool,
+    interpolate: bool,
+    trim: bool,
+    empty: Option<String>,
     crate_: Option<Vec<TokenTree>>,
     env_name: Option<Literal>,
+    /// Names tried in order when `env_name` is absent.
+    fallbacks: Vec<Literal>,
+    /// The prefix of variables collected by a map flag.
+    prefix: Option<Literal>,
+    /// Whether it is a list from `NAME_0`, `NAME_1`, ..., and the maximum length if any.
+    indexed: Option<Option<Literal>>,
+    /// The policy of empty components of a `Vec<PathBuf>` flag.
+    empty_paths: Option<String>,
     parser: Option<Vec<TokenTree>>,
+    /// Accepted values, which are listed in generated documents.
+    variants: Vec<String>,
 }
 
 impl FlagContext {
-    fn new(mut attr: VecDeque<TokenTree>, mut items: VecDeque<TokenTree>) -> Self {
+    fn new(
+        mut attr: VecDeque<TokenTree>,
+        mut items: VecDeque<TokenTree>,
+    ) -> Result<Self, TokenStream> {
         let mut default = vec![];
+        let mut profiles = vec![];
+        let mut profile_span = None;
+        let mut default_str = None;
+        let mut default_fn = None;
+        let mut depends_on = vec![];
+        let mut secret = false;
+        let mut allow_file = false;
+        let mut interpolate = false;
+        let mut trim = false;
+        let mut empty = None;
         let mut crate_ = None;
         let mut env_name = None;
+        let mut fallbacks = vec![];
+        let mut prefix = None;
+        let mut indexed = None;
+        let mut empty_paths = None;
         let mut parser = None;
+        let mut variants = vec![];
         while let Some(tt) = attr.pop_front() {
             match tt {
-                TokenTree::Ident(id) if id.to_string() == "default" => {
+                TokenTree::Ident(id) if id.to_string() == "default" => match attr.pop_front() {
+                    Some(TokenTree::Punct(p)) if p.as_char() == '=' => {
+                        collect_until(&mut default, &mut attr, is_comma);
+                    }
+                    Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+                        let mut profile: Vec<TokenTree> = g.stream().into_iter().collect();
+                        let profile = match (profile.pop(), profile.is_empty()) {
+                            (Some(TokenTree::Ident(id)), true) => id.to_string(),
+                            _ => {
+                                panic!("A profile must be named by an identifier.");
+                            }
+                        };
+                        match attr.pop_front() {
+                            Some(TokenTree::Punct(p)) if p.as_char() == '=' => (),
+                            _ => {
+                                panic!("\"default({})\" must be assigned a value.", profile);
+                            }
+                        }
+                        let mut c = vec![];
+                        collect_until(&mut c, &mut attr, is_comma);
+                        profiles.push((profile, c));
+                        profile_span.get_or_insert(id.span());
+                    }
+                    _ => {
+                        panic!("\"default\" must be assigned a value.");
+                    }
+                },
+                TokenTree::Ident(id) if id.to_string() == "default_str" => {
+                    match attr.pop_front() {
+                        Some(TokenTree::Punct(p)) if p.as_char() == '=' => (),
+                        _ => {
+                            panic!("\"default_str\" must be assigned a string literal.");
+                        }
+                    }
+                    let mut c = vec![];
+                    collect_until(&mut c, &mut attr, is_comma);
+                    match (c.pop(), c.is_empty()) {
+                        (Some(TokenTree::Literal(l)), true) => {
+                            default_str = Some(l);
+                        }
+                        _ => {
+                            panic!("\"default_str\" must be assigned a string literal.");
+                        }
+                    }
+                }
+                TokenTree::Ident(id) if id.to_string() == "default_fn" => {
+                    match attr.pop_front() {
+                        Some(TokenTree::Punct(p)) if p.as_char() == '=' => (),
+                        _ => {
+                            panic!("\"default_fn\" must be assigned a function.");
+                        }
+                    }
+                    let mut c = vec![];
+                    collect_until(&mut c, &mut attr, is_comma);
+                    default_fn = Some(c);
+                }
+                TokenTree::Ident(id) if id.to_string() == "depends_on" => {
                     match attr.pop_front() {
                         Some(TokenTree::Punct(p)) if p.as_char() == '=' => (),
                         _ => {
-                            panic!("\"default\" must be assigned a value.");
+                            panic!("\"depends_on\" must be assigned a list of flags.");
+                        }
+                    }
+                    let mut c = vec![];
+                    collect_until(&mut c, &mut attr, is_comma);
+                    match (c.pop(), c.is_empty()) {
+                        (Some(TokenTree::Group(g)), true)
+                            if g.delimiter() == Delimiter::Bracket =>
+                        {
+                            let mut flags: VecDeque<TokenTree> = g.stream().into_iter().collect();
+                            while !flags.is_empty() {
+                                let mut flag = vec![];
+                                collect_until(&mut flag, &mut flags, is_comma);
+                                depends_on.push(flag);
+                            }
+                        }
+                        _ => {
+                            panic!("\"depends_on\" must be assigned a list of flags.");
+                        }
+                    }
+                }
+                TokenTree::Ident(id) if id.to_string() == "secret" => {
+                    match attr.pop_front() {
+                        None => (),
+                        Some(tt) if is_comma(&tt) => (),
+                        _ => {
+                            panic!("\"secret\" takes no value.");
+                        }
+                    }
+                    secret = true;
+                }
+                TokenTree::Ident(id) if id.to_string() == "allow_file" => {
+                    match attr.pop_front() {
+                        None => (),
+                        Some(tt) if is_comma(&tt) => (),
+                        _ => {
+                            panic!("\"allow_file\" takes no value.");
+                        }
+                    }
+                    allow_file = true;
+                }
+                TokenTree::Ident(id) if id.to_string() == "interpolate" => {
+                    match attr.pop_front() {
+                        None => (),
+                        Some(tt) if is_comma(&tt) => (),
+                        _ => {
+                            panic!("\"interpolate\" takes no value.");
+                        }
+                    }
+                    interpolate = true;
+                }
+                TokenTree::Ident(id) if id.to_string() == "trim" => match attr.pop_front() {
+                    None => trim = true,
+                    Some(tt) if is_comma(&tt) => trim = true,
+                    Some(TokenTree::Punct(p)) if p.as_char() == '=' => {
+                        let mut c = vec![];
+                        collect_until(&mut c, &mut attr, is_comma);
+                        trim = match c.as_slice() {
+                            [TokenTree::Ident(id)] if id.to_string() == "true" => true,
+                            [TokenTree::Ident(id)] if id.to_string() == "false" => false,
+                            _ => {
+                                panic!("\"trim\" must be assigned a bool.");
+                            }
+                        };
+                    }
+                    _ => {
+                        panic!("\"trim\" must be assigned a bool.");
+                    }
+                },
+                TokenTree::Ident(id) if id.to_string() == "empty" => {
+                    match attr.pop_front() {
+                        Some(TokenTree::Punct(p)) if p.as_char() == '=' => (),
+                        _ => {
+                            panic!("\"empty\" must be assigned a string literal.");
+                        }
+                    }
+                    let mut c = vec![];
+                    collect_until(&mut c, &mut attr, is_comma);
+                    let policy = match c.as_slice() {
+                        [TokenTree::Literal(l)] => string_value(l),
+                        _ => None,
+                    };
+                    match policy.as_deref() {
+                        Some("unset") => empty = Some("Unset".to_string()),
+                        Some("error") => empty = Some("Error".to_string()),
+                        Some("value") => empty = Some("Value".to_string()),
+                        _ => {
+                            panic!("\"empty\" must be one of \"unset\", \"error\" and \"value\".");
                         }
                     }
-                    collect_until(&mut default, &mut attr, |tt| match tt {
-                        TokenTree::Punct(p) if p.as_char() == ',' => true,
-                        _ => false,
-                    });
                 }
                 TokenTree::Ident(id) if id.to_string() == "crate" => {
                     match attr.pop_front() {
@@ -49,10 +227,7 @@ impl FlagContext {
                         }
                     }
                     let mut c = vec![];
-                    collect_until(&mut c, &mut attr, |tt| match tt {
-                        TokenTree::Punct(p) if p.as_char() == ',' => true,
-                        _ => false,
-                    });
+                    collect_until(&mut c, &mut attr, is_comma);
                     crate_ = Some(c);
                 }
                 TokenTree::Ident(id) if id.to_string() == "env_name" => {
@@ -63,10 +238,7 @@ impl FlagContext {
                         }
                     }
                     let mut c = vec![];
-                    collect_until(&mut c, &mut attr, |tt| match tt {
-                        TokenTree::Punct(p) if p.as_char() == ',' => true,
-                        _ => false,
-                    });
+                    collect_until(&mut c, &mut attr, is_comma);
                     assert_eq!(
                         c.len(),
                         1,
@@ -76,11 +248,115 @@ impl FlagContext {
                         Some(TokenTree::Literal(l)) => {
                             env_name = Some(l);
                         }
+                        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Bracket => {
+                            let mut names = vec![];
+                            for tt in g.stream() {
+                                match tt {
+                                    TokenTree::Literal(l) => names.push(l),
+                                    tt if is_comma(&tt) => (),
+                                    _ => {
+                                        panic!("\"env_name\" must be assigned string literals.");
+                                    }
+                                }
+                            }
+                            if names.is_empty() {
+                                panic!("\"env_name\" must be assigned at least one name.");
+                            }
+                            env_name = Some(names.remove(0));
+                            fallbacks = names;
+                        }
                         _ => {
                             panic!("\"env_name\" must be assigned a string literal.");
                         }
                     }
                 }
+                TokenTree::Ident(id) if id.to_string() == "variants" => {
+                    match attr.pop_front() {
+                        Some(TokenTree::Punct(p)) if p.as_char() == '=' => (),
+                        _ => {
+                            panic!("\"variants\" must be assigned a list of string literals.");
+                        }
+                    }
+                    let mut c = vec![];
+                    collect_until(&mut c, &mut attr, is_comma);
+                    match c.pop() {
+                        Some(TokenTree::Group(g))
+                            if c.is_empty() && g.delimiter() == Delimiter::Bracket =>
+                        {
+                            for tt in g.stream() {
+                                match &tt {
+                                    TokenTree::Literal(l) if string_value(l).is_some() => {
+                                        variants.extend(string_value(l));
+                                    }
+                                    tt if is_comma(tt) => (),
+                                    _ => {
+                                        panic!("\"variants\" must be assigned a list of string literals.");
+                                    }
+                                }
+                            }
+                        }
+                        _ => {
+                            panic!("\"variants\" must be assigned a list of string literals.");
+                        }
+                    }
+                }
+                TokenTree::Ident(id) if id.to_string() == "prefix" => {
+                    match attr.pop_front() {
+                        Some(TokenTree::Punct(p)) if p.as_char() == '=' => (),
+                        _ => {
+                            panic!("\"prefix\" must be assigned a string literal.");
+                        }
+                    }
+                    let mut c = vec![];
+                    collect_until(&mut c, &mut attr, is_comma);
+                    match c.as_slice() {
+                        [TokenTree::Literal(l)] => prefix = Some(l.clone()),
+                        _ => {
+                            panic!("\"prefix\" must be assigned a string literal.");
+                        }
+                    }
+                }
+                TokenTree::Ident(id) if id.to_string() == "indexed" => match attr.pop_front() {
+                    None => indexed = Some(None),
+                    Some(tt) if is_comma(&tt) => indexed = Some(None),
+                    Some(TokenTree::Punct(p)) if p.as_char() == '=' => {
+                        let mut c = vec![];
+                        collect_until(&mut c, &mut attr, is_comma);
+                        match c.as_slice() {
+                            [TokenTree::Literal(l)] => indexed = Some(Some(l.clone())),
+                            _ => {
+                                panic!("\"indexed\" must be assigned the maximum length.");
+                            }
+                        }
+                    }
+                    _ => {
+                        panic!("\"indexed\" must be assigned the maximum length.");
+                    }
+                },
+                TokenTree::Ident(id) if id.to_string() == "empty_paths" => {
+                    match attr.pop_front() {
+                        Some(TokenTree::Punct(p)) if p.as_char() == '=' => (),
+                        _ => {
+                            panic!("\"empty_paths\" must be assigned a string literal.");
+                        }
+                    }
+                    let mut c = vec![];
+                    collect_until(&mut c, &mut attr, is_comma);
+                    let policy = match c.as_slice() {
+                        [TokenTree::Literal(l)] => string_value(l),
+                        _ => None,
+                    };
+                    match policy.as_deref() {
+                        Some("drop") => empty_paths = Some("Drop".to_string()),
+                        Some("error") => empty_paths = Some("Error".to_string()),
+                        Some("keep") => empty_paths = Some("Keep".to_string()),
+                        _ => {
+                            panic!(
+                                "\"empty_paths\" must be one of \"drop\", \"error\" and \"keep\"."
+                            );
+                        }
+                    }
+                }
                 TokenTree::Ident(id) if id.to_string() == "parser" => {
                     match attr.pop_front() {
                         Some(TokenTree::Punct(p)) if p.as_char() == '=' => (),
@@ -89,23 +365,16 @@ impl FlagContext {
                         }
                     }
                     let mut c = vec![];
-                    collect_until(&mut c, &mut attr, |tt| match tt {
-                        TokenTree::Punct(p) if p.as_char() == ',' => true,
-                        _ => false,
-                    });
+                    collect_until(&mut c, &mut attr, is_comma);
                     parser = Some(c);
                 }
                 _ => {
-                    panic!("Unknown attr: {}", tt.to_string());
+                    panic!("Unknown attr: {}", tt);
                 }
             }
         }
         let mut preamble = vec![];
-        collect_until(&mut preamble, &mut items, |tt| match tt {
-            TokenTree::Punct(p) if p.as_char() == ':' => true,
-            _ => false,
-        })
-        .unwrap_or_else(|| {
+        collect_until(&mut preamble, &mut items, |tt| is_punct(tt, ':')).unwrap_or_else(|| {
             panic!("Type is required for an env flag.");
         });
         let Some(TokenTree::Ident(ident)) = preamble.pop() else {
@@ -119,16 +388,117 @@ impl FlagContext {
         }
         let mut base_type: VecDeque<TokenTree> = items.into_iter().collect();
         let has_option = filter_out_option(&mut base_type);
-        Self {
+        let defaults = [
+            !default.is_empty(),
+            default_str.is_some(),
+            default_fn.is_some(),
+        ];
+        let defaults = defaults.into_iter().filter(|x| *x).count();
+        if has_option && defaults > 0 {
+            panic!("An Option flag cannot have default values.");
+        }
+        if let (Some(span), true) = (profile_span, default.is_empty()) {
+            let message = if default_str.is_some() || default_fn.is_some() {
+                "Profile defaults are supported only by \"default\", neither \"default_str\" nor \"default_fn\"."
+            } else {
+                "Profile defaults require \"default\" as the fallback when no profile matches."
+            };
+            return Err(compile_error(message, span));
+        }
+        if !depends_on.is_empty() && default_fn.is_none() {
+            panic!("\"depends_on\" requires \"default_fn\".");
+        }
+        if defaults > 1 {
+            panic!("\"default\", \"default_str\" and \"default_fn\" are exclusive.");
+        }
+        if is_variants(&base_type) && parser.is_none() {
+            if !default.is_empty() {
+                panic!("A Variants flag cannot have \"default\", since Variants cannot be const. Use \"default_str\" instead.");
+            }
+            match default_str.as_ref().and_then(string_value) {
+                Some(default) if !variants.is_empty() => {
+                    for (name, _) in default.split(',').filter_map(|x| x.split_once(':')) {
+                        if !variants.iter().any(|x| x == name.trim()) {
+                            panic!(
+                                "Variant \"{}\" in \"default_str\" is not listed in \"variants\".",
+                                name.trim()
+                            );
+                        }
+                    }
+                }
+                Some(_) => (),
+                None if variants.is_empty() => {
+                    panic!("A Variants flag without \"default_str\" must list names of variants by \"variants\".");
+                }
+                None => (),
+            }
+        }
+        let has_policies = allow_file || interpolate || trim || empty.is_some();
+        if is_path_list(&base_type) && parser.is_none() {
+            if has_option || defaults > 0 {
+                panic!("A path list flag cannot be an Option or have default values.");
+            }
+            if prefix.is_some() || indexed.is_some() || !fallbacks.is_empty() {
+                panic!("A path list flag cannot have \"prefix\", \"indexed\" or fallback names.");
+            }
+            if has_policies {
+                panic!(
+                    "A path list flag cannot have \"allow_file\", \"interpolate\", \"trim\" or \"empty\"."
+                );
+            }
+        } else if empty_paths.is_some() {
+            panic!("\"empty_paths\" applies only to flags of type Vec<PathBuf> without parsers.");
+        }
+        if indexed.is_some() {
+            if has_option || defaults > 0 {
+                panic!("An indexed flag cannot be an Option or have default values.");
+            }
+            if prefix.is_some() || !fallbacks.is_empty() {
+                panic!("An indexed flag cannot have \"prefix\" or fallback names.");
+            }
+            if has_policies {
+                panic!(
+                    "An indexed flag cannot have \"allow_file\", \"interpolate\", \"trim\" or \"empty\"."
+                );
+            }
+        }
+        if prefix.is_some() {
+            if has_option || defaults > 0 {
+                panic!("A prefix flag cannot be an Option or have default values.");
+            }
+            if env_name.is_some() {
+                panic!("\"prefix\" and \"env_name\" are exclusive.");
+            }
+            if has_policies {
+                panic!(
+                    "A prefix flag cannot have \"allow_file\", \"interpolate\", \"trim\" or \"empty\"."
+                );
+            }
+        }
+        Ok(Self {
             preamble,
             ident,
             base_type,
             has_option,
             default,
+            profiles,
+            default_str,
+            default_fn,
+            depends_on,
+            secret,
+            allow_file,
+            interpolate,
+            trim,
+            empty,
             crate_,
             env_name,
+            fallbacks,
+            prefix,
+            indexed,
+            empty_paths,
             parser,
-        }
+            variants,
+        })
     }
 }
 
@@ -149,17 +519,163 @@ where
     None
 }
 
+/// Renders tokens of a type without spaces, e.g., `Vec<String>`.
+fn type_name<'a>(tts: impl Iterator<Item = &'a TokenTree>) -> String {
+    let mut res = String::new();
+    for tt in tts {
+        match tt {
+            TokenTree::Group(g) => {
+                let (open, close) = match g.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+                let inner: Vec<TokenTree> = g.stream().into_iter().collect();
+                res.push_str(open);
+                res.push_str(&type_name(inner.iter()));
+                res.push_str(close);
+            }
+            TokenTree::Punct(p) if p.as_char() == ',' => res.push_str(", "),
+            tt => res.push_str(&tt.to_string()),
+        }
+    }
+    res
+}
+
+/// Emits `var.field =`.
+fn assign(out: &mut TokenStream, var: &str, field: &str) {
+    out.extend([
+        TokenTree::Ident(Ident::new(var, Span::mixed_site())),
+        TokenTree::Punct(Punct::new('.', Spacing::Alone)),
+        TokenTree::Ident(Ident::new(field, Span::mixed_site())),
+        TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+    ]);
+}
+
+/// Emits `{ items let mut var = init; fields var }`, where `fields` assign fields of `var`,
+/// since structs whose fields may grow cannot be built by literals.
+fn build(var: &str, items: TokenStream, init: TokenStream, fields: TokenStream) -> TokenTree {
+    let mut res = items;
+    res.extend([
+        TokenTree::Ident(Ident::new("let", Span::mixed_site())),
+        TokenTree::Ident(Ident::new("mut", Span::mixed_site())),
+        TokenTree::Ident(Ident::new(var, Span::mixed_site())),
+        TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+    ]);
+    res.extend(init);
+    res.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+    res.extend(fields);
+    res.extend([TokenTree::Ident(Ident::new(var, Span::mixed_site()))]);
+    TokenTree::Group(Group::new(Delimiter::Brace, res))
+}
+
+/// `compile_error!(message);` pointing at `span`.
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let message = TokenTree::Literal(Literal::string(message));
+    [
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("core", span)),
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, message.into())),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]
+    .into_iter()
+    .map(|mut tt| {
+        tt.set_span(span);
+        tt
+    })
+    .collect()
+}
+
+fn is_punct(tt: &TokenTree, ch: char) -> bool {
+    matches!(tt, TokenTree::Punct(p) if p.as_char() == ch)
+}
+
+/// The value of a plain string literal without escapes.
+fn string_value(lit: &Literal) -> Option<String> {
+    let lit = lit.to_string();
+    let res = lit.strip_prefix('"')?.strip_suffix('"')?;
+    if res.contains('\\') {
+        return None;
+    }
+    Some(res.to_string())
+}
+
+fn is_variants(base_type: &VecDeque<TokenTree>) -> bool {
+    matches!(base_type.back(), Some(TokenTree::Ident(id)) if id.to_string() == "Variants")
+}
+
+fn is_comma(tt: &TokenTree) -> bool {
+    is_punct(tt, ',')
+}
+
+/// Whether the type is `Vec<PathBuf>`, which is a list of paths like `PATH`.
+fn is_path_list(base_type: &VecDeque<TokenTree>) -> bool {
+    let idents: Vec<String> = base_type
+        .iter()
+        .filter_map(|tt| match tt {
+            TokenTree::Ident(id) => Some(id.to_string()),
+            _ => None,
+        })
+        .collect();
+    matches!(idents.first(), Some(x) if x == "Vec")
+        && matches!(idents.last(), Some(x) if x == "PathBuf")
+}
+
+/// The type of items of a list type like `Vec<T>`.
+fn list_item_type(base_type: &VecDeque<TokenTree>) -> VecDeque<TokenTree> {
+    let mut res: VecDeque<TokenTree> = base_type
+        .iter()
+        .skip_while(|tt| !is_punct(tt, '<'))
+        .skip(1)
+        .cloned()
+        .collect();
+    match res.pop_back() {
+        Some(tt) if is_punct(&tt, '>') && !res.is_empty() => res,
+        _ => {
+            panic!("An indexed flag must be of type Vec<T>.");
+        }
+    }
+}
+
+/// The type of values of a map type like `BTreeMap<String, T>`.
+fn map_value_type(base_type: &VecDeque<TokenTree>) -> VecDeque<TokenTree> {
+    let mut depth = 0;
+    let mut res = None;
+    for tt in base_type.iter() {
+        match tt {
+            tt if is_punct(tt, '<') => depth += 1,
+            tt if is_punct(tt, '>') => depth -= 1,
+            tt if depth == 1 && is_comma(tt) && res.is_none() => {
+                res = Some(VecDeque::new());
+                continue;
+            }
+            _ => (),
+        }
+        if let Some(ref mut res) = res {
+            if depth > 0 {
+                res.push_back(tt.clone());
+            }
+        }
+    }
+    match res {
+        Some(res) if !res.is_empty() => res,
+        _ => {
+            panic!("A prefix flag must be of type BTreeMap<String, T>.");
+        }
+    }
+}
+
 fn filter_out_option(base_type: &mut VecDeque<TokenTree>) -> bool {
     let has_option = base_type
         .iter()
-        .take_while(|tt| match tt {
-            TokenTree::Punct(p) if p.as_char() == '<' => false,
-            _ => true,
-        })
-        .any(|tt| match tt {
-            TokenTree::Ident(id) if id.to_string() == "Option" => true,
-            _ => false,
-        });
+        .take_while(|tt| !is_punct(tt, '<'))
+        .any(|tt| matches!(tt, TokenTree::Ident(id) if id.to_string() == "Option"));
     if has_option {
         match base_type.pop_back() {
             Some(TokenTree::Punct(p)) if p.as_char() == '>' => (),
@@ -168,29 +684,61 @@ fn filter_out_option(base_type: &mut VecDeque<TokenTree>) -> bool {
             }
         }
         let mut not_used = vec![];
-        collect_until(&mut not_used, base_type, |tt| match tt {
-            TokenTree::Punct(p) if p.as_char() == '<' => true,
-            _ => false,
-        })
-        .unwrap_or_else(|| {
+        collect_until(&mut not_used, base_type, |tt| is_punct(tt, '<')).unwrap_or_else(|| {
             panic!("Option has no opening delimiter.");
         });
     }
     has_option
 }
 
-impl Into<TokenStream> for FlagContext {
-    fn into(self) -> TokenStream {
+impl From<FlagContext> for TokenStream {
+    fn from(flag: FlagContext) -> Self {
         let mut res = TokenStream::new();
-        res.extend(self.preamble.iter().cloned());
+        res.extend(flag.preamble.iter().cloned());
         res.extend([
-            TokenTree::Ident(self.ident.clone()),
+            TokenTree::Ident(flag.ident.clone()),
             TokenTree::Punct(Punct::new(':', Spacing::Alone)),
         ]);
-        self.wrapped_type(&mut res);
-        res.extend([TokenTree::Punct(Punct::new('=', Spacing::Alone))]);
-        self.init_value(&mut res);
+        if is_path_list(&flag.base_type) && flag.parser.is_none() {
+            flag.path_list(&mut res);
+        } else if flag.prefix.is_some() {
+            flag.prefix_map(&mut res);
+        } else if flag.indexed.is_some() {
+            flag.indexed_list(&mut res);
+        } else if flag.is_templated() {
+            flag.crate_item(&mut res, "Templated");
+            res.extend([TokenTree::Punct(Punct::new('<', Spacing::Alone))]);
+            flag.wrapped_type(&mut res);
+            res.extend([
+                TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+                TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+            ]);
+            flag.crate_item(&mut res, "Templated");
+            let mut init = TokenStream::new();
+            init.extend([
+                TokenTree::Ident(Ident::new("flag", Span::mixed_site())),
+                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            ]);
+            flag.init_value(&mut init);
+            res.extend([TokenTree::Group(Group::new(Delimiter::Brace, init))]);
+        } else {
+            flag.wrapped_type(&mut res);
+            res.extend([TokenTree::Punct(Punct::new('=', Spacing::Alone))]);
+            flag.init_value(&mut res);
+        }
         res.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+        if is_path_list(&flag.base_type) && flag.parser.is_none() {
+            flag.declared(&mut res, |out| flag.crate_item(out, "PathList"));
+        } else if flag.prefix.is_some() {
+            let value = FlagContext {
+                base_type: map_value_type(&flag.base_type),
+                ..flag.clone()
+            };
+            flag.declared(&mut res, |out| {
+                flag.crate_item(out, "PrefixMap");
+                value.wo_default_type_params(out);
+            });
+        }
         res
     }
 }
@@ -200,6 +748,15 @@ impl FlagContext {
         if self.has_option {
             self.wo_default_base(out);
             self.wo_default_type_params(out);
+        } else if self.default_str.is_some() {
+            self.crate_item(out, "EnvFlagWithDefaultStr");
+            self.wo_default_type_params(out);
+        } else if self.default_fn.is_some() {
+            self.crate_item(out, "EnvFlagWithDefaultFn");
+            self.wo_default_type_params(out);
+        } else if self.default.is_empty() {
+            self.crate_item(out, "EnvFlagRequired");
+            self.wo_default_type_params(out);
         } else {
             self.w_default_base(out);
             self.w_default_type_params(out);
@@ -209,44 +766,137 @@ impl FlagContext {
     fn init_value(&self, out: &mut TokenStream) {
         if self.has_option {
             self.init_wo_default(out);
-        } else {
-            self.w_default_base(out);
+        } else if let Some(ref default_str) = self.default_str {
+            self.crate_item(out, "EnvFlagWithDefaultStr");
             out.extend([
                 TokenTree::Punct(Punct::new(':', Spacing::Joint)),
                 TokenTree::Punct(Punct::new(':', Spacing::Alone)),
             ]);
-            self.w_default_type_params(out);
-            out.extend([self.init_w_default()]);
+            self.wo_default_type_params(out);
+            out.extend([self.init_w_default(|out| {
+                out.extend([TokenTree::Literal(default_str.clone())]);
+            })]);
+        } else if let Some(ref default_fn) = self.default_fn {
+            self.crate_item(out, "EnvFlagWithDefaultFn");
+            out.extend([
+                TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            ]);
+            self.wo_default_type_params(out);
+            out.extend([self.init_w_default(|out| {
+                out.extend(default_fn.iter().cloned());
+            })]);
+        } else if self.default.is_empty() {
+            self.crate_item(out, "EnvFlagRequired");
+            out.extend([
+                TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            ]);
+            self.wo_default_type_params(out);
+            let mut res = TokenStream::new();
+            res.extend([
+                TokenTree::Ident(Ident::new("env", Span::mixed_site())),
+                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            ]);
+            self.init_wo_default(&mut res);
+            out.extend([TokenTree::Group(Group::new(Delimiter::Brace, res))]);
+        } else {
+            let mut init = TokenStream::new();
+            self.w_default_base(&mut init);
+            init.extend([
+                TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            ]);
+            self.w_default_type_params(&mut init);
+            init.extend([
+                TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+                TokenTree::Ident(Ident::new("new", Span::mixed_site())),
+            ]);
+            let mut args = TokenStream::new();
+            self.init_wo_default(&mut args);
+            args.extend([
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                TokenTree::Ident(Ident::new("DEFAULT", Span::mixed_site())),
+            ]);
+            init.extend([TokenTree::Group(Group::new(Delimiter::Parenthesis, args))]);
+            let mut fields = TokenStream::new();
+            assign(&mut fields, "flag", "profiles");
+            fields.extend([
+                TokenTree::Ident(Ident::new("PROFILES", Span::mixed_site())),
+                TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+            ]);
+            let mut items = TokenStream::new();
+            self.defaults(&mut items);
+            out.extend([build("flag", items, init, fields)]);
         }
     }
 
     fn init_wo_default(&self, out: &mut TokenStream) {
-        self.wo_default_base(out);
-        out.extend([
+        let mut init = TokenStream::new();
+        self.wo_default_base(&mut init);
+        init.extend([
             TokenTree::Punct(Punct::new(':', Spacing::Joint)),
             TokenTree::Punct(Punct::new(':', Spacing::Alone)),
         ]);
-        self.wo_default_type_params(out);
-        out.extend([{
-            let mut res = TokenStream::new();
-            res.extend([
-                TokenTree::Ident(Ident::new("key", Span::mixed_site())),
-                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
-            ]);
-            self.env_name(&mut res);
-            res.extend([
+        self.wo_default_type_params(&mut init);
+        init.extend([
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("new", Span::mixed_site())),
+        ]);
+        let mut args = TokenStream::new();
+        self.env_name(&mut args);
+        args.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+        self.parser(&mut args);
+        init.extend([TokenTree::Group(Group::new(Delimiter::Parenthesis, args))]);
+
+        let mut fields = TokenStream::new();
+        assign(&mut fields, "env", "fallbacks");
+        let mut fallbacks = TokenStream::new();
+        for name in self.fallbacks.iter() {
+            fallbacks.extend([
+                TokenTree::Literal(name.clone()),
                 TokenTree::Punct(Punct::new(',', Spacing::Alone)),
-                TokenTree::Ident(Ident::new("parser", Span::mixed_site())),
-                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
             ]);
-            self.parser(&mut res);
-            res.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
-            let g = Group::new(Delimiter::Brace, res);
-            TokenTree::Group(g)
-        }]);
+        }
+        fields.extend([
+            TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+            TokenTree::Group(Group::new(Delimiter::Bracket, fallbacks)),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+        for (field, value) in [
+            ("allow_file", self.allow_file),
+            ("interpolate", self.interpolate),
+            ("trim", self.trim),
+        ] {
+            assign(&mut fields, "env", field);
+            fields.extend([
+                TokenTree::Ident(Ident::new(
+                    if value { "true" } else { "false" },
+                    Span::mixed_site(),
+                )),
+                TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+            ]);
+        }
+        assign(&mut fields, "env", "empty");
+        self.crate_item(&mut fields, "Empty");
+        fields.extend([
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new(
+                self.empty.as_deref().unwrap_or("Value"),
+                Span::mixed_site(),
+            )),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+        assign(&mut fields, "env", "meta");
+        self.meta(&mut fields);
+        fields.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+        out.extend([build("env", TokenStream::new(), init, fields)]);
     }
 
-    fn init_w_default(&self) -> TokenTree {
+    fn init_w_default(&self, default_value: impl FnOnce(&mut TokenStream)) -> TokenTree {
         let mut res = TokenStream::new();
         res.extend([
             TokenTree::Ident(Ident::new("env", Span::mixed_site())),
@@ -258,27 +908,26 @@ impl FlagContext {
             TokenTree::Ident(Ident::new("default", Span::mixed_site())),
             TokenTree::Punct(Punct::new(':', Spacing::Alone)),
         ]);
-        self.default_value(&mut res);
+        default_value(&mut res);
         res.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
         let g = Group::new(Delimiter::Brace, res);
         TokenTree::Group(g)
     }
 
     fn wo_default_base(&self, out: &mut TokenStream) {
-        self.crate_name(out);
-        out.extend([
-            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
-            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
-            TokenTree::Ident(Ident::new("EnvFlag", Span::mixed_site())),
-        ]);
+        self.crate_item(out, "EnvFlag");
     }
 
     fn w_default_base(&self, out: &mut TokenStream) {
+        self.crate_item(out, "EnvFlagWithDefault");
+    }
+
+    fn crate_item(&self, out: &mut TokenStream, flag_type: &str) {
         self.crate_name(out);
         out.extend([
             TokenTree::Punct(Punct::new(':', Spacing::Joint)),
             TokenTree::Punct(Punct::new(':', Spacing::Alone)),
-            TokenTree::Ident(Ident::new("EnvFlagWithDefault", Span::mixed_site())),
+            TokenTree::Ident(Ident::new(flag_type, Span::mixed_site())),
         ]);
     }
 
@@ -297,6 +946,11 @@ impl FlagContext {
 
     fn default_type_param(&self, out: &mut TokenStream) {
         out.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+        self.default_type(out);
+    }
+
+    /// The type which the default is a reference to, i.e., `DefaultT` of `EnvFlagWithDefault`.
+    fn default_type(&self, out: &mut TokenStream) {
         match self.single_token_base_type() {
             Some(TokenTree::Ident(id)) if id.to_string() == "String" => {
                 out.extend([TokenTree::Ident(Ident::new("str", Span::mixed_site()))]);
@@ -323,6 +977,177 @@ impl FlagContext {
         }
     }
 
+    /// Emits `const _: fn(<F as Declared>::Declared) -> T = |x| x;`
+    /// for a flag of type `F` but declared as `T`,
+    /// which checks the declared type and keeps its imports used.
+    fn declared(&self, out: &mut TokenStream, flag_type: impl FnOnce(&mut TokenStream)) {
+        let mut declared = TokenStream::new();
+        declared.extend([TokenTree::Punct(Punct::new('<', Spacing::Alone))]);
+        flag_type(&mut declared);
+        declared.extend([TokenTree::Ident(Ident::new("as", Span::mixed_site()))]);
+        self.crate_item(&mut declared, "Declared");
+        declared.extend([
+            TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("Declared", Span::mixed_site())),
+        ]);
+        out.extend([
+            TokenTree::Ident(Ident::new("const", Span::mixed_site())),
+            TokenTree::Ident(Ident::new("_", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("fn", Span::mixed_site())),
+            TokenTree::Group(Group::new(Delimiter::Parenthesis, declared)),
+            TokenTree::Punct(Punct::new('-', Spacing::Joint)),
+            TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+        ]);
+        out.extend(self.base_type.iter().cloned());
+        let x = || TokenTree::Ident(Ident::new("x", Span::mixed_site()));
+        out.extend([
+            TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+            TokenTree::Punct(Punct::new('|', Spacing::Alone)),
+            x(),
+            TokenTree::Punct(Punct::new('|', Spacing::Alone)),
+            x(),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+    }
+
+    /// Emits the type and the value of a flag declared like
+    /// `#[envflag(prefix="FEATURE_")] const FEATURES: BTreeMap<String, bool>;`.
+    fn prefix_map(&self, out: &mut TokenStream) {
+        let value = FlagContext {
+            base_type: map_value_type(&self.base_type),
+            ..self.clone()
+        };
+        self.crate_item(out, "PrefixMap");
+        value.wo_default_type_params(out);
+        out.extend([TokenTree::Punct(Punct::new('=', Spacing::Alone))]);
+        self.crate_item(out, "PrefixMap");
+        out.extend([
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        value.wo_default_type_params(out);
+        let mut res = TokenStream::new();
+        res.extend([
+            TokenTree::Ident(Ident::new("prefix", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Literal(self.prefix.clone().unwrap()),
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("parser", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        value.parser(&mut res);
+        res.extend([
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("meta", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        self.meta(&mut res);
+        res.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+        out.extend([TokenTree::Group(Group::new(Delimiter::Brace, res))]);
+    }
+
+    /// Emits the type and the value of a flag declared like
+    /// `const SEARCH_PATH: Vec<PathBuf>;`.
+    fn path_list(&self, out: &mut TokenStream) {
+        self.crate_item(out, "PathList");
+        out.extend([TokenTree::Punct(Punct::new('=', Spacing::Alone))]);
+        self.crate_item(out, "PathList");
+        let mut res = TokenStream::new();
+        res.extend([
+            TokenTree::Ident(Ident::new("key", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        self.env_name(&mut res);
+        res.extend([
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("empty_paths", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        self.crate_item(&mut res, "EmptyPaths");
+        res.extend([
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new(
+                self.empty_paths.as_deref().unwrap_or("Drop"),
+                Span::mixed_site(),
+            )),
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("meta", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        self.meta(&mut res);
+        res.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+        out.extend([TokenTree::Group(Group::new(Delimiter::Brace, res))]);
+    }
+
+    /// Emits the type and the value of a flag declared like
+    /// `#[envflag(indexed)] const DATABASES: Vec<String>;`.
+    fn indexed_list(&self, out: &mut TokenStream) {
+        let item = FlagContext {
+            base_type: list_item_type(&self.base_type),
+            ..self.clone()
+        };
+        self.crate_item(out, "IndexedList");
+        item.wo_default_type_params(out);
+        out.extend([TokenTree::Punct(Punct::new('=', Spacing::Alone))]);
+        self.crate_item(out, "IndexedList");
+        out.extend([
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        item.wo_default_type_params(out);
+        let mut res = TokenStream::new();
+        res.extend([
+            TokenTree::Ident(Ident::new("key", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        self.env_name(&mut res);
+        res.extend([
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("parser", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        item.parser(&mut res);
+        res.extend([
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("max", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        match self.indexed {
+            Some(Some(ref max)) => {
+                res.extend([
+                    TokenTree::Ident(Ident::new("Some", Span::mixed_site())),
+                    TokenTree::Group(Group::new(
+                        Delimiter::Parenthesis,
+                        TokenStream::from(TokenTree::Literal(max.clone())),
+                    )),
+                ]);
+            }
+            _ => {
+                res.extend([TokenTree::Ident(Ident::new("None", Span::mixed_site()))]);
+            }
+        }
+        res.extend([
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("meta", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        self.meta(&mut res);
+        res.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+        out.extend([TokenTree::Group(Group::new(Delimiter::Brace, res))]);
+    }
+
+    /// Whether `env_name` is a template like `"SHARD_{}_HOST"`.
+    fn is_templated(&self) -> bool {
+        self.env_name
+            .as_ref()
+            .and_then(string_value)
+            .is_some_and(|x| x.contains("{}"))
+    }
+
     fn env_name(&self, out: &mut TokenStream) {
         out.extend([if let Some(ref env_name) = self.env_name {
             TokenTree::Literal(env_name.clone())
@@ -345,6 +1170,8 @@ impl FlagContext {
                 Some(TokenTree::Ident(id)) if id.to_string() == "i64" => "i64_parser",
                 Some(TokenTree::Ident(id)) if id.to_string() == "f64" => "f64_parser",
                 Some(TokenTree::Ident(id)) if id.to_string() == "bool" => "bool_parser",
+                Some(TokenTree::Ident(id)) if id.to_string() == "Rollout" => "rollout_parser",
+                Some(TokenTree::Ident(id)) if id.to_string() == "Variants" => "variants_parser",
                 _ => {
                     panic!("Unknown base type to parse.");
                 }
@@ -356,20 +1183,234 @@ impl FlagContext {
         }
     }
 
-    fn default_value(&self, out: &mut TokenStream) {
+    fn meta(&self, out: &mut TokenStream) {
+        let mut init = TokenStream::new();
+        self.crate_item(&mut init, "Meta");
+        init.extend([
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("DEFAULT", Span::mixed_site())),
+        ]);
+        let mut res = TokenStream::new();
+        assign(&mut res, "meta", "depends_on");
+        res.extend([TokenTree::Punct(Punct::new('&', Spacing::Alone))]);
+        let mut deps = TokenStream::new();
+        for dep in self.depends_on.iter() {
+            deps.extend(dep.iter().cloned());
+            deps.extend([
+                TokenTree::Punct(Punct::new('.', Spacing::Alone)),
+                TokenTree::Ident(Ident::new("key", Span::mixed_site())),
+                TokenTree::Group(Group::new(Delimiter::Parenthesis, TokenStream::new())),
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            ]);
+        }
+        res.extend([
+            TokenTree::Group(Group::new(Delimiter::Bracket, deps)),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+        assign(&mut res, "meta", "secret");
+        res.extend([
+            TokenTree::Ident(Ident::new(
+                if self.secret { "true" } else { "false" },
+                Span::mixed_site(),
+            )),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+        assign(&mut res, "meta", "doc");
+        self.doc(&mut res);
+        res.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+        assign(&mut res, "meta", "type_name");
+        res.extend([
+            TokenTree::Literal(Literal::string(&type_name(self.base_type.iter()))),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+        assign(&mut res, "meta", "default");
+        match self.default_text() {
+            Some(text) => {
+                res.extend([
+                    TokenTree::Ident(Ident::new("Some", Span::mixed_site())),
+                    TokenTree::Group(Group::new(
+                        Delimiter::Parenthesis,
+                        TokenStream::from(TokenTree::Literal(text)),
+                    )),
+                ]);
+            }
+            None => {
+                res.extend([TokenTree::Ident(Ident::new("None", Span::mixed_site()))]);
+            }
+        }
+        res.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+        assign(&mut res, "meta", "variants");
+        let mut variants = TokenStream::new();
+        for name in self.variant_names() {
+            variants.extend([
+                TokenTree::Literal(Literal::string(&name)),
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            ]);
+        }
+        res.extend([
+            TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+            TokenTree::Group(Group::new(Delimiter::Bracket, variants)),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+        out.extend([build("meta", TokenStream::new(), init, res)]);
+    }
+
+    /// Concatenates doc comments of the flag, which are `#[doc = "..."]` in the preamble.
+    fn doc(&self, out: &mut TokenStream) {
+        let mut docs = vec![];
+        let mut preamble = self.preamble.iter();
+        while let Some(tt) = preamble.next() {
+            if !is_punct(tt, '#') {
+                continue;
+            }
+            let Some(TokenTree::Group(g)) = preamble.next() else {
+                continue;
+            };
+            let attr: Vec<TokenTree> = g.stream().into_iter().collect();
+            match attr.as_slice() {
+                [TokenTree::Ident(id), eq, TokenTree::Literal(l)]
+                    if id.to_string() == "doc" && is_punct(eq, '=') =>
+                {
+                    docs.push(l.clone());
+                }
+                _ => (),
+            }
+        }
+        let mut args = TokenStream::new();
+        for (i, doc) in docs.into_iter().enumerate() {
+            if i > 0 {
+                args.extend([
+                    TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                    TokenTree::Literal(Literal::string("\n")),
+                    TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                ]);
+            }
+            args.extend([TokenTree::Literal(doc)]);
+        }
+        out.extend([
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("core", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("concat", Span::mixed_site())),
+            TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+            TokenTree::Group(Group::new(Delimiter::Parenthesis, args)),
+        ]);
+    }
+
+    /// The default value in the syntax of env variables, if it is known at compile time.
+    fn default_text(&self) -> Option<Literal> {
+        if let Some(ref default_str) = self.default_str {
+            return Some(default_str.clone());
+        }
+        match self.default.as_slice() {
+            [] => None,
+            [TokenTree::Literal(l)] if l.to_string().ends_with('"') => Some(l.clone()),
+            tts if tts.iter().all(|tt| match tt {
+                TokenTree::Literal(_) => true,
+                TokenTree::Ident(id) => ["true", "false"].contains(&id.to_string().as_str()),
+                tt => is_punct(tt, '-'),
+            }) =>
+            {
+                Some(Literal::string(&type_name(tts.iter())))
+            }
+            _ => None,
+        }
+    }
+
+    /// Values listed by `variants`, or else names of variants in the default of
+    /// a `Variants` flag, which is written like `default_str="control:50,treatment:50"`.
+    fn variant_names(&self) -> Vec<String> {
+        if !self.variants.is_empty() {
+            return self.variants.clone();
+        }
+        if !is_variants(&self.base_type) {
+            return vec![];
+        }
+        let Some(default) = self.default_str.as_ref().and_then(string_value) else {
+            return vec![];
+        };
+        default
+            .split(',')
+            .filter_map(|x| x.split_once(':'))
+            .map(|(name, _)| name.trim().to_string())
+            .collect()
+    }
+
+    fn default_value(&self, value: &[TokenTree], out: &mut TokenStream) {
         match self.single_token_base_type() {
             Some(TokenTree::Ident(id))
                 if ["i64", "f64", "bool"].contains(&id.to_string().as_str()) =>
             {
                 out.extend([TokenTree::Punct(Punct::new('&', Spacing::Alone))]);
-                out.extend(self.default.iter().cloned());
+                out.extend(value.iter().cloned());
             }
             _ => {
-                out.extend(self.default.iter().cloned());
+                out.extend(value.iter().cloned());
             }
         }
     }
 
+    /// Emits `const DEFAULT: &T = ...; const PROFILES: &[(&str, &T)] = &[...];`,
+    /// where references to defaults live as long as the flag.
+    fn defaults(&self, out: &mut TokenStream) {
+        let mut default_type = TokenStream::new();
+        default_type.extend([TokenTree::Punct(Punct::new('&', Spacing::Alone))]);
+        self.default_type(&mut default_type);
+        out.extend([
+            TokenTree::Ident(Ident::new("const", Span::mixed_site())),
+            TokenTree::Ident(Ident::new("DEFAULT", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ]);
+        out.extend(default_type.clone());
+        out.extend([TokenTree::Punct(Punct::new('=', Spacing::Alone))]);
+        self.default_value(&self.default, out);
+        out.extend([
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("const", Span::mixed_site())),
+            TokenTree::Ident(Ident::new("PROFILES", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+        ]);
+        let mut profile_type = TokenStream::new();
+        profile_type.extend([
+            TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+            TokenTree::Ident(Ident::new("str", Span::mixed_site())),
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        ]);
+        profile_type.extend(default_type);
+        out.extend([
+            TokenTree::Group(Group::new(
+                Delimiter::Bracket,
+                TokenStream::from(TokenTree::Group(Group::new(
+                    Delimiter::Parenthesis,
+                    profile_type,
+                ))),
+            )),
+            TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+            TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+        ]);
+        let mut profiles = TokenStream::new();
+        for (profile, value) in self.profiles.iter() {
+            let mut res = TokenStream::new();
+            res.extend([
+                TokenTree::Literal(Literal::string(profile)),
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            ]);
+            self.default_value(value, &mut res);
+            profiles.extend([
+                TokenTree::Group(Group::new(Delimiter::Parenthesis, res)),
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            ]);
+        }
+        out.extend([
+            TokenTree::Group(Group::new(Delimiter::Bracket, profiles)),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+    }
+
     fn single_token_base_type(&self) -> Option<&TokenTree> {
         if self.base_type.len() != 1 {
             None
@@ -0,0 +1,92 @@
+use crate::{Layer, Source};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Values loaded from a TOML or JSON config file.
+///
+/// Keys at the top level are names of env variables.
+/// Values must be strings, numbers or booleans,
+/// and non-string values are handed to parsers in their textual forms.
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    path: PathBuf,
+    vars: BTreeMap<String, String>,
+}
+
+impl ConfigFile {
+    /// Loads a config file, whose format is told by the extension,
+    /// i.e., `.toml` or `.json`.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Fail to read {}", path.display()))?;
+        match path.extension().and_then(|x| x.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::parse_toml(path, &content),
+            #[cfg(feature = "json")]
+            Some("json") => Self::parse_json(path, &content),
+            _ => anyhow::bail!("Unsupported config file: {}", path.display()),
+        }
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn parse_toml(path: impl AsRef<Path>, content: &str) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let table: toml::Table =
+            toml::from_str(content).with_context(|| format!("Fail to parse {}", path.display()))?;
+        let mut vars = BTreeMap::new();
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::String(x) => x,
+                toml::Value::Integer(x) => x.to_string(),
+                toml::Value::Float(x) => x.to_string(),
+                toml::Value::Boolean(x) => x.to_string(),
+                _ => anyhow::bail!("Unsupported value of \"{}\" in {}", key, path.display()),
+            };
+            vars.insert(key, value);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            vars,
+        })
+    }
+
+    #[cfg(feature = "json")]
+    pub fn parse_json(path: impl AsRef<Path>, content: &str) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let object: serde_json::Map<String, serde_json::Value> = serde_json::from_str(content)
+            .with_context(|| format!("Fail to parse {}", path.display()))?;
+        let mut vars = BTreeMap::new();
+        for (key, value) in object {
+            let value = match value {
+                serde_json::Value::String(x) => x,
+                serde_json::Value::Number(x) => x.to_string(),
+                serde_json::Value::Bool(x) => x.to_string(),
+                _ => anyhow::bail!("Unsupported value of \"{}\" in {}", key, path.display()),
+            };
+            vars.insert(key, value);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            vars,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Layer for ConfigFile {
+    fn lookup(&self, key: &str) -> anyhow::Result<Option<(String, Source)>> {
+        let res = self.vars.get(key).map(|x| {
+            let source = Source::File {
+                path: self.path.clone(),
+                key: key.to_string(),
+            };
+            (x.clone(), source)
+        });
+        Ok(res)
+    }
+}
@@ -0,0 +1,221 @@
+//! Dumps of effective values of flags, e.g., to log at startup or to attach to bug reports.
+//!
+//! ```
+//! use rs_envflag::diagnostics::{dump, Format};
+//! use rs_envflag::Flag;
+//! use rs_envflag_macros::*;
+//!
+//! #[envflag(default=8080)]
+//! const PORT: i64;
+//!
+//! #[envflag(secret)]
+//! const DB_PASSWORD: Option<String>;
+//!
+//! const FLAGS: &[&dyn Flag] = &[&PORT, &DB_PASSWORD];
+//!
+//! println!("{}", dump(FLAGS, Format::Text));
+//! ```
+
+use crate::{active_profile, Flag, Meta, Source};
+use std::fmt::{Debug, Write};
+
+/// What a flag is fetched as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis {
+    pub key: &'static str,
+    /// The value in its `Debug` form, or redacted for secret flags.
+    /// It is `None` if the flag is absent or fails to fetch.
+    pub value: Option<String>,
+    /// It is `None` if the flag is absent or fails to fetch.
+    pub source: Option<Source>,
+    /// The error if the flag fails to fetch, e.g., a parsing error.
+    pub error: Option<String>,
+}
+
+const REDACTED: &str = "<redacted>";
+
+impl Diagnosis {
+    pub(crate) fn new<T: Debug>(
+        meta: &Meta,
+        key: &'static str,
+        res: anyhow::Result<Option<(T, Source)>>,
+    ) -> Self {
+        match res {
+            Ok(Some((value, source))) => {
+                let value = if meta.secret {
+                    REDACTED.to_string()
+                } else {
+                    format!("{:?}", value)
+                };
+                Self {
+                    key,
+                    value: Some(value),
+                    source: Some(source),
+                    error: None,
+                }
+            }
+            Ok(None) => Self {
+                key,
+                value: None,
+                source: None,
+                error: None,
+            },
+            Err(e) => Self {
+                key,
+                value: None,
+                source: None,
+                error: Some(format!("{:#}", e)),
+            },
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn is_defaulted(&self) -> bool {
+        matches!(self.source, Some(Source::Default { .. }))
+    }
+
+    fn status(&self) -> &str {
+        if self.is_ok() {
+            "ok"
+        } else {
+            "error"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A human-readable table.
+    Text,
+    Json,
+    /// One line of `key=value` pairs per flag, a.k.a., logfmt.
+    KeyValue,
+}
+
+/// Fetches all flags and dumps them in `format`, along with the active profile.
+pub fn dump(flags: &[&dyn Flag], format: Format) -> String {
+    let profile = active_profile().ok().flatten();
+    let diagnoses: Vec<Diagnosis> = flags.iter().map(|x| x.diagnose()).collect();
+    match format {
+        Format::Text => dump_text(profile.as_deref(), &diagnoses),
+        Format::Json => dump_json(profile.as_deref(), &diagnoses),
+        Format::KeyValue => dump_key_value(profile.as_deref(), &diagnoses),
+    }
+}
+
+fn dump_text(profile: Option<&str>, diagnoses: &[Diagnosis]) -> String {
+    let header = ["KEY", "VALUE", "SOURCE", "STATUS"].map(|x| x.to_string());
+    let rows: Vec<[String; 4]> = diagnoses
+        .iter()
+        .map(|x| {
+            [
+                x.key.to_string(),
+                x.value.clone().unwrap_or_else(|| "-".to_string()),
+                x.source
+                    .as_ref()
+                    .map_or_else(|| "-".to_string(), |x| x.to_string()),
+                match x.error {
+                    None => "ok".to_string(),
+                    Some(ref e) => format!("error: {}", e),
+                },
+            ]
+        })
+        .collect();
+    let mut widths = header.clone().map(|x| x.len());
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut res = String::new();
+    writeln!(res, "active profile: {}", profile.unwrap_or("-")).unwrap();
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let mut line = String::new();
+        for (cell, width) in row.iter().zip(widths.iter()) {
+            write!(line, "{:width$}  ", cell, width = width).unwrap();
+        }
+        writeln!(res, "{}", line.trim_end()).unwrap();
+    }
+    res
+}
+
+fn dump_json(profile: Option<&str>, diagnoses: &[Diagnosis]) -> String {
+    let mut res = String::new();
+    write!(res, "{{\"profile\":{},\"flags\":[", json_opt(profile)).unwrap();
+    for (i, x) in diagnoses.iter().enumerate() {
+        if i > 0 {
+            res.push(',');
+        }
+        write!(
+            res,
+            "{{\"key\":{},\"value\":{},\"source\":{},\"defaulted\":{},\"status\":{},\"error\":{}}}",
+            json_str(x.key),
+            json_opt(x.value.as_deref()),
+            json_opt(x.source.as_ref().map(|x| x.to_string()).as_deref()),
+            x.is_defaulted(),
+            json_str(x.status()),
+            json_opt(x.error.as_deref()),
+        )
+        .unwrap();
+    }
+    res.push_str("]}");
+    res
+}
+
+fn dump_key_value(profile: Option<&str>, diagnoses: &[Diagnosis]) -> String {
+    let mut res = String::new();
+    writeln!(res, "profile={}", logfmt_str(profile.unwrap_or(""))).unwrap();
+    for x in diagnoses.iter() {
+        write!(
+            res,
+            "key={} value={} source={} defaulted={} status={}",
+            logfmt_str(x.key),
+            logfmt_str(x.value.as_deref().unwrap_or("")),
+            logfmt_str(&x.source.as_ref().map(|x| x.to_string()).unwrap_or_default()),
+            x.is_defaulted(),
+            x.status(),
+        )
+        .unwrap();
+        if let Some(ref e) = x.error {
+            write!(res, " error={}", logfmt_str(e)).unwrap();
+        }
+        res.push('\n');
+    }
+    res
+}
+
+pub(crate) fn json_str(x: &str) -> String {
+    let mut res = String::with_capacity(x.len() + 2);
+    res.push('"');
+    for c in x.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(res, "\\u{:04x}", c as u32).unwrap(),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+fn json_opt(x: Option<&str>) -> String {
+    x.map_or_else(|| "null".to_string(), json_str)
+}
+
+fn logfmt_str(x: &str) -> String {
+    if !x.is_empty()
+        && x.chars()
+            .all(|c| !c.is_whitespace() && !c.is_control() && c != '"' && c != '=')
+    {
+        x.to_string()
+    } else {
+        json_str(x)
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{Layer, Source};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Variables loaded from a `.env` file.
+///
+/// It never touches the process environment.
+/// To look up the process environment first and then the file,
+/// stack them as `(Env, dotenv)`.
+///
+/// The syntax is the common one of `.env` files:
+///
+/// *   `KEY=value` per line, optionally prefixed by `export`.
+/// *   Lines starting with `#` are comments.
+///     So is anything following ` #` in unquoted values.
+/// *   Unquoted values are trimmed.
+/// *   Values in single quotes are taken literally.
+/// *   Values in double quotes support escapes `\n`, `\r`, `\t`, `\"`, `\\` and `\$`.
+/// *   Quoted values can span multiple lines.
+#[derive(Debug, Clone, Default)]
+pub struct DotEnv {
+    path: PathBuf,
+    vars: BTreeMap<String, String>,
+}
+
+impl DotEnv {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Fail to read {}", path.display()))?;
+        Self::parse(&path.display().to_string(), &content)
+    }
+
+    /// Parses content of a `.env` file.
+    /// `origin` is the file name to show in errors.
+    pub fn parse(origin: &str, content: &str) -> anyhow::Result<Self> {
+        let mut parser = Parser {
+            origin,
+            rest: content,
+            line: 1,
+        };
+        let mut vars = BTreeMap::new();
+        while let Some((key, value)) = parser.next_var()? {
+            vars.insert(key, value);
+        }
+        Ok(Self {
+            path: PathBuf::from(origin),
+            vars,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.vars.get(key).map(|x| x.as_str())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+impl Layer for DotEnv {
+    fn lookup(&self, key: &str) -> anyhow::Result<Option<(String, Source)>> {
+        let res = self.vars.get(key).map(|x| {
+            let source = Source::File {
+                path: self.path.clone(),
+                key: key.to_string(),
+            };
+            (x.clone(), source)
+        });
+        Ok(res)
+    }
+}
+
+struct Parser<'a> {
+    origin: &'a str,
+    rest: &'a str,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn next_var(&mut self) -> anyhow::Result<Option<(String, String)>> {
+        loop {
+            self.skip_blanks();
+            match self.peek() {
+                None => return Ok(None),
+                Some('\n') => self.bump(),
+                Some('#') => self.skip_line(),
+                Some(_) => break,
+            }
+        }
+        if let Some(rest) = self.rest.strip_prefix("export") {
+            if rest.starts_with([' ', '\t']) {
+                self.rest = rest;
+                self.skip_blanks();
+            }
+        }
+        let key = self.key()?;
+        self.skip_blanks();
+        if self.peek() != Some('=') {
+            return Err(self.error(format!("Expect '=' after \"{}\"", key)));
+        }
+        self.bump();
+        let blank = self.rest.starts_with([' ', '\t']);
+        self.skip_blanks();
+        let value = match self.peek() {
+            Some('#') if blank => {
+                self.skip_line();
+                return Ok(Some((key, String::new())));
+            }
+            Some('\'') => self.single_quoted()?,
+            Some('"') => self.double_quoted()?,
+            _ => return Ok(Some((key, self.unquoted()))),
+        };
+        self.skip_blanks();
+        match self.peek() {
+            None | Some('\n') | Some('#') => self.skip_line(),
+            Some(_) => {
+                return Err(self.error(format!(
+                    "Unexpected characters after the quoted value of \"{}\"",
+                    key
+                )));
+            }
+        }
+        Ok(Some((key, value)))
+    }
+
+    fn key(&mut self) -> anyhow::Result<String> {
+        let len = self
+            .rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(self.rest.len());
+        let key = &self.rest[..len];
+        if key.is_empty() || key.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(self.error("Invalid key".to_string()));
+        }
+        self.rest = &self.rest[len..];
+        Ok(key.to_string())
+    }
+
+    fn unquoted(&mut self) -> String {
+        let len = self.rest.find('\n').unwrap_or(self.rest.len());
+        let mut value = &self.rest[..len];
+        if let Some(pos) = find_comment(value) {
+            value = &value[..pos];
+        }
+        self.rest = &self.rest[len..];
+        value.trim().to_string()
+    }
+
+    fn single_quoted(&mut self) -> anyhow::Result<String> {
+        let start = self.line;
+        self.bump();
+        let Some(len) = self.rest.find('\'') else {
+            return Err(self.error_at(start, "Unterminated single quote".to_string()));
+        };
+        let value = &self.rest[..len];
+        self.line += value.matches('\n').count();
+        self.rest = &self.rest[len + 1..];
+        Ok(value.to_string())
+    }
+
+    fn double_quoted(&mut self) -> anyhow::Result<String> {
+        let start = self.line;
+        self.bump();
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(self.error_at(start, "Unterminated double quote".to_string()));
+                }
+                Some('"') => {
+                    self.bump();
+                    return Ok(value);
+                }
+                Some('\\') => {
+                    self.bump();
+                    match self.peek() {
+                        Some('n') => value.push('\n'),
+                        Some('r') => value.push('\r'),
+                        Some('t') => value.push('\t'),
+                        Some(c @ ('"' | '\\' | '$')) => value.push(c),
+                        Some(c) => {
+                            value.push('\\');
+                            value.push(c);
+                        }
+                        None => {
+                            return Err(
+                                self.error_at(start, "Unterminated double quote".to_string())
+                            );
+                        }
+                    }
+                    self.bump();
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            if c == '\n' {
+                self.line += 1;
+            }
+            self.rest = &self.rest[c.len_utf8()..];
+        }
+    }
+
+    fn skip_blanks(&mut self) {
+        self.rest = self.rest.trim_start_matches([' ', '\t', '\r']);
+    }
+
+    fn skip_line(&mut self) {
+        let len = self.rest.find('\n').unwrap_or(self.rest.len());
+        self.rest = &self.rest[len..];
+    }
+
+    fn error(&self, msg: String) -> anyhow::Error {
+        self.error_at(self.line, msg)
+    }
+
+    fn error_at(&self, line: usize, msg: String) -> anyhow::Error {
+        anyhow::Error::msg(format!("{}:{}: {}", self.origin, line, msg))
+    }
+}
+
+/// Finds where an inline comment, i.e., a `#` following a whitespace, starts.
+fn find_comment(value: &str) -> Option<usize> {
+    value
+        .char_indices()
+        .zip(value.chars().skip(1))
+        .find(|((_, c), next)| (*c == ' ' || *c == '\t') && *next == '#')
+        .map(|((pos, _), _)| pos)
+}
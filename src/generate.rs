@@ -0,0 +1,391 @@
+//! Generators of documents from definitions of flags.
+//!
+//! ```
+//! use rs_envflag::generate::env_example;
+//! use rs_envflag::Flag;
+//! use rs_envflag_macros::*;
+//!
+//! /// the port to listen on
+//! #[envflag(default=8080)]
+//! const PORT: i64;
+//!
+//! const FLAGS: &[&dyn Flag] = &[&PORT];
+//!
+//! if std::env::args().any(|x| x == "--env-example") {
+//!     print!("{}", env_example(FLAGS));
+//! }
+//! ```
+
+use crate::diagnostics::json_str;
+use crate::{Flag, Kind, Meta};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Renders a `.env.example` file, listing all flags with their doc comments,
+/// types and defaults.
+///
+/// Flags with known defaults are assigned their defaults,
+/// and the others, as well as secret ones, are commented out.
+pub fn env_example(flags: &[&dyn Flag]) -> String {
+    let mut res = String::new();
+    for (i, flag) in flags.iter().enumerate() {
+        if i > 0 {
+            res.push('\n');
+        }
+        let meta = flag.meta();
+        for line in meta.doc_lines() {
+            writeln!(res, "{}", format!("# {}", line).trim_end()).unwrap();
+        }
+        write!(res, "# type: {}", meta.type_name).unwrap();
+        match (flag.has_default(), meta.default) {
+            (false, _) if flag.required() => res.push_str(", required"),
+            (false, _) => res.push_str(", optional"),
+            (true, Some(default)) if !meta.secret => {
+                write!(res, ", default: {}", default).unwrap();
+            }
+            (true, _) => res.push_str(", defaulted"),
+        }
+        if meta.secret {
+            res.push_str(", secret");
+        }
+        if flag.kind() == Kind::PathList {
+            write!(res, ", separated by {}", PATH_SEPARATOR).unwrap();
+        }
+        res.push('\n');
+        match flag.kind() {
+            Kind::Single | Kind::PathList => match meta.default {
+                Some(default) if !meta.secret => {
+                    writeln!(res, "{}={}", flag.key(), quote_env_value(default)).unwrap();
+                }
+                // Empty values would not fall back to defaults, so they are commented out.
+                _ => writeln!(res, "# {}=", flag.key()).unwrap(),
+            },
+            // Names are patterns, which are commented out.
+            Kind::Templated | Kind::Prefixed | Kind::Indexed => {
+                writeln!(res, "# {}=", display_key(*flag)).unwrap();
+            }
+        }
+    }
+    res
+}
+
+const PATH_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
+
+/// The name of a flag, with patterns in angle brackets, e.g., `DATABASES_<N>`.
+fn display_key(flag: &dyn Flag) -> String {
+    let key = flag.key();
+    match flag.kind() {
+        Kind::Single | Kind::PathList => key.to_string(),
+        Kind::Templated => key.replace("{}", "<INDEX>"),
+        Kind::Prefixed => format!("{}<NAME>", key),
+        Kind::Indexed => format!("{}_<N>", key),
+    }
+}
+
+/// Writes the result of `env_example()` to `path`.
+pub fn write_env_example(flags: &[&dyn Flag], path: impl AsRef<Path>) -> std::io::Result<()> {
+    std::fs::write(path, env_example(flags))
+}
+
+/// Quotes a value in the syntax of `.env` files if necessary.
+fn quote_env_value(x: &str) -> String {
+    let plain = x
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_.,:/@+%".contains(c));
+    if plain {
+        return x.to_string();
+    }
+    let mut res = String::from("\"");
+    for c in x.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '$' => res.push_str("\\$"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+/// Renders a JSON Schema of environment maps, i.e., objects from names of
+/// env variables to their values, e.g., to check `.env` files in editors.
+///
+/// *   Values are strings as in env variables and `.env` files,
+///     and those of `bool`, integers, floats, `Rollout` and `Variants` are
+///     constrained to patterns of their syntax, but not to ranges of integers.
+/// *   Values listed by `variants` are given as `enum`, and names of variants of
+///     `Variants` flags are kept in their patterns.
+/// *   Flags of templated names, prefixes and indices are listed in `patternProperties`.
+/// *   Doc comments become descriptions, and secret flags are marked `writeOnly`.
+/// *   Flags without defaults which are not `Option`s are `required`.
+pub fn json_schema(flags: &[&dyn Flag]) -> String {
+    let (singles, patterns): (Vec<&dyn Flag>, Vec<&dyn Flag>) = flags
+        .iter()
+        .partition(|x| matches!(x.kind(), Kind::Single | Kind::PathList));
+    let mut res = String::new();
+    res.push_str("{\n");
+    res.push_str("  \"$schema\": \"https://json-schema.org/draft/2020-12/schema\",\n");
+    res.push_str("  \"type\": \"object\",\n");
+    res.push_str("  \"properties\": {");
+    for (i, flag) in singles.iter().enumerate() {
+        if i > 0 {
+            res.push(',');
+        }
+        write_schema(&mut res, &json_str(flag.key()), *flag);
+    }
+    if !singles.is_empty() {
+        res.push_str("\n  ");
+    }
+    res.push('}');
+    if !patterns.is_empty() {
+        res.push_str(",\n  \"patternProperties\": {");
+        for (i, flag) in patterns.iter().enumerate() {
+            if i > 0 {
+                res.push(',');
+            }
+            write_schema(&mut res, &json_str(&key_pattern(*flag)), *flag);
+        }
+        res.push_str("\n  }");
+    }
+    let required = singles
+        .iter()
+        .filter(|x| x.required())
+        .map(|x| json_str(x.key()))
+        .collect::<Vec<_>>();
+    write!(res, ",\n  \"required\": [{}]", required.join(", ")).unwrap();
+    res.push_str("\n}\n");
+    res
+}
+
+/// Writes `name` and the schema of values of `flag`.
+fn write_schema(res: &mut String, name: &str, flag: &dyn Flag) {
+    let meta = flag.meta();
+    let type_name = value_type(flag);
+    let mut props = vec![];
+    props.push(("type", json_str("string")));
+    if let Some(pattern) = value_pattern(meta, type_name) {
+        props.push(("pattern", json_str(&pattern)));
+    }
+    if !meta.variants.is_empty() && !is_variants(type_name) {
+        let values = meta.variants.iter().map(|x| json_str(x));
+        props.push((
+            "enum",
+            format!("[{}]", values.collect::<Vec<_>>().join(", ")),
+        ));
+    }
+    let doc = meta.doc_lines().collect::<Vec<_>>().join("\n");
+    if !doc.is_empty() {
+        props.push(("description", json_str(doc.trim())));
+    }
+    match meta.default {
+        Some(default) if !meta.secret => props.push(("default", json_str(default))),
+        _ => (),
+    }
+    if meta.secret {
+        props.push(("writeOnly", "true".to_string()));
+    }
+    write!(res, "\n    {}: {{", name).unwrap();
+    for (j, (name, value)) in props.iter().enumerate() {
+        if j > 0 {
+            res.push(',');
+        }
+        write!(res, "\n      \"{}\": {}", name, value).unwrap();
+    }
+    res.push_str("\n    }");
+}
+
+/// The type of the value of each env variable of `flag`.
+fn value_type(flag: &dyn Flag) -> &'static str {
+    let type_name = flag.meta().type_name;
+    match flag.kind() {
+        Kind::Single | Kind::Templated => type_name,
+        Kind::PathList => "String",
+        // e.g., `BTreeMap<String, bool>`
+        Kind::Prefixed => type_name
+            .split_once('<')
+            .and_then(|(_, x)| x.strip_suffix('>')?.split_once(','))
+            .map_or("String", |(_, x)| x.trim()),
+        Kind::Indexed => list_item_type(type_name).unwrap_or("String"),
+    }
+}
+
+/// The regular expression of names of env variables of `flag`.
+fn key_pattern(flag: &dyn Flag) -> String {
+    let key = flag.key();
+    match flag.kind() {
+        Kind::Single | Kind::PathList => format!("^{}$", regex_escape(key)),
+        Kind::Templated => {
+            let (prefix, suffix) = key.split_once("{}").unwrap_or((key, ""));
+            format!("^{}.+{}$", regex_escape(prefix), regex_escape(suffix))
+        }
+        Kind::Prefixed => format!("^{}.+$", regex_escape(key)),
+        Kind::Indexed => format!("^{}_(0|[1-9][0-9]*)$", regex_escape(key)),
+    }
+}
+
+/// The regular expression of values of built-in types, as they are parsed by `FromStr`,
+/// `Rollout` and `Variants`.
+fn value_pattern(meta: &Meta, type_name: &str) -> Option<String> {
+    let res = match type_name.rsplit("::").next().unwrap_or(type_name) {
+        "bool" => "^(true|false)$",
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => "^[+-]?[0-9]+$",
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => r"^\+?[0-9]+$",
+        "f32" | "f64" => {
+            r"^[+-]?(([0-9]+\.?[0-9]*|\.[0-9]+)([eE][+-]?[0-9]+)?|[iI][nN][fF]([iI][nN][iI][tT][yY])?|[nN][aA][nN])$"
+        }
+        // e.g., `25%`, `12.5`, or `true`
+        "Rollout" => r"^\s*(true|false|(100(\.0*)?|[0-9]{1,2}(\.[0-9]*)?)\s*%?)\s*$",
+        // e.g., `control:50,treatment:50`
+        "Variants" => {
+            let name = if meta.variants.is_empty() {
+                r"[^,:\s][^,:]*".to_string()
+            } else {
+                let names = meta.variants.iter().map(|x| regex_escape(x));
+                format!("({})", names.collect::<Vec<_>>().join("|"))
+            };
+            let variant = format!(r"\s*{}\s*:\s*[0-9]+\s*", name);
+            return Some(format!("^{}(,{})*$", variant, variant));
+        }
+        _ => return None,
+    };
+    Some(res.to_string())
+}
+
+fn is_variants(type_name: &str) -> bool {
+    type_name.rsplit("::").next() == Some("Variants")
+}
+
+fn regex_escape(x: &str) -> String {
+    let mut res = String::with_capacity(x.len());
+    for c in x.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    res
+}
+
+fn list_item_type(type_name: &str) -> Option<&str> {
+    type_name
+        .strip_prefix("Vec<")
+        .and_then(|x| x.strip_suffix('>'))
+}
+
+/// Renders a Markdown table of flags, with their types, defaults and doc comments.
+pub fn markdown(flags: &[&dyn Flag]) -> String {
+    let mut res = String::new();
+    res.push_str("| Variable | Type | Default | Description |\n");
+    res.push_str("| --- | --- | --- | --- |\n");
+    for flag in flags.iter() {
+        let meta = flag.meta();
+        let default = match default_text(*flag) {
+            DefaultText::Value(x) => format!("`{}`", markdown_escape(x)),
+            x => x.to_string(),
+        };
+        let doc = meta
+            .doc_lines()
+            .map(markdown_escape)
+            .collect::<Vec<_>>()
+            .join("<br>");
+        writeln!(
+            res,
+            "| `{}` | `{}` | {} | {} |",
+            display_key(*flag),
+            markdown_escape(meta.type_name),
+            default,
+            doc.trim_end_matches("<br>"),
+        )
+        .unwrap();
+    }
+    res
+}
+
+/// Renders the `ENVIRONMENT` section of man pages in roff.
+pub fn man(flags: &[&dyn Flag]) -> String {
+    let mut res = String::new();
+    res.push_str(".SH ENVIRONMENT\n");
+    for flag in flags.iter() {
+        let meta = flag.meta();
+        writeln!(res, ".TP\n.B {}", roff_escape(&display_key(*flag))).unwrap();
+        let mut paragraph = false;
+        for line in meta.doc_lines() {
+            if line.trim().is_empty() {
+                if paragraph {
+                    res.push_str(".IP\n");
+                    paragraph = false;
+                }
+                continue;
+            }
+            writeln!(res, "{}", roff_line(line)).unwrap();
+            paragraph = true;
+        }
+        if paragraph {
+            res.push_str(".br\n");
+        }
+        let mut line = format!("Type: {}.", meta.type_name);
+        match default_text(*flag) {
+            DefaultText::Value(x) => write!(line, " Default: {}.", x).unwrap(),
+            DefaultText::Computed => line.push_str(" Default: computed."),
+            DefaultText::Secret => line.push_str(" Secret."),
+            DefaultText::Required => line.push_str(" Required."),
+            DefaultText::None => line.push_str(" Optional."),
+        }
+        writeln!(res, "{}", roff_line(&line)).unwrap();
+    }
+    res
+}
+
+enum DefaultText {
+    Value(&'static str),
+    Computed,
+    Secret,
+    Required,
+    None,
+}
+
+impl std::fmt::Display for DefaultText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefaultText::Value(x) => write!(f, "{}", x),
+            DefaultText::Computed => write!(f, "computed"),
+            DefaultText::Secret => write!(f, "secret"),
+            DefaultText::Required => write!(f, "required"),
+            DefaultText::None => write!(f, "-"),
+        }
+    }
+}
+
+fn default_text(flag: &dyn Flag) -> DefaultText {
+    let meta = flag.meta();
+    match (flag.has_default(), meta.default) {
+        (false, _) if meta.secret => DefaultText::Secret,
+        (false, _) if flag.required() => DefaultText::Required,
+        (false, _) => DefaultText::None,
+        (true, _) if meta.secret => DefaultText::Secret,
+        (true, Some(x)) => DefaultText::Value(x),
+        (true, None) => DefaultText::Computed,
+    }
+}
+
+fn markdown_escape(x: &str) -> String {
+    x.replace('|', "\\|")
+}
+
+fn roff_escape(x: &str) -> String {
+    x.replace('\\', "\\e").replace('-', "\\-")
+}
+
+/// Escapes a line of text, which must not start with control characters.
+fn roff_line(x: &str) -> String {
+    let res = roff_escape(x.trim());
+    if res.starts_with(['.', '\'']) {
+        format!("\\&{}", res)
+    } else {
+        res
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{Diagnosis, Flag, Installed, Kind, Layer, Meta, Source};
+use anyhow::Context;
+use std::fmt::Debug;
+
+/// How many indices after the first missing one are checked for gaps in any layer.
+/// Beyond them, gaps are found only among variables in the process environment.
+const GAP_PROBE: usize = 16;
+
+/// A list flag read from `NAME_0`, `NAME_1`, ... until the first missing index,
+/// for items which cannot be separated by commas, e.g., connection strings.
+///
+/// Indices set after a gap, or spelled with a leading zero like `NAME_01`, are errors.
+/// Since layers cannot be enumerated, they are found if the indices are
+/// within 16 of the first missing one, or set in the process environment.
+pub struct IndexedList<T> {
+    pub key: &'static str,
+    pub parser: fn(key: &str, val: &str) -> anyhow::Result<T>,
+    /// The maximum length, if any.
+    pub max: Option<usize>,
+    pub meta: Meta,
+}
+
+impl<T> IndexedList<T> {
+    pub const fn key(&self) -> &'static str {
+        self.key
+    }
+
+    /// The name of the env variable for `index`.
+    pub fn key_for(&self, index: usize) -> String {
+        format!("{}_{}", self.key, index)
+    }
+
+    pub fn fetch(&'static self) -> anyhow::Result<Vec<T>> {
+        self.fetch_from(&Installed)
+    }
+
+    pub fn fetch_from<L: Layer + ?Sized>(&self, layer: &L) -> anyhow::Result<Vec<T>> {
+        Ok(self
+            .fetch_from_with_source(layer)?
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect())
+    }
+
+    pub fn fetch_with_source(&'static self) -> anyhow::Result<Vec<(T, Source)>> {
+        self.fetch_from_with_source(&Installed)
+    }
+
+    pub fn fetch_from_with_source<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+    ) -> anyhow::Result<Vec<(T, Source)>> {
+        let mut res = vec![];
+        loop {
+            let key = self.key_for(res.len());
+            if self.max == Some(res.len()) {
+                if layer.lookup(&key)?.is_some() {
+                    anyhow::bail!(
+                        "Too many ENV {}_*: {} is set but at most {} items are allowed",
+                        self.key,
+                        key,
+                        res.len()
+                    );
+                }
+                break;
+            }
+            let Some((val, source)) = layer.lookup(&key)? else {
+                break;
+            };
+            let duplicate = format!("{}_0{}", self.key, res.len());
+            if layer.lookup(&duplicate)?.is_some() {
+                anyhow::bail!("Duplicate ENV {} and {}", key, duplicate);
+            }
+            let x = (self.parser)(&key, &val).with_context(|| {
+                format!("Fail to parse item {} of ENV {}_*", res.len(), self.key)
+            })?;
+            res.push((x, source));
+        }
+        let missing = res.len();
+        let mut rest: Vec<(usize, String)> = (missing..=missing + GAP_PROBE)
+            .flat_map(|i| {
+                let padded = (i, format!("{}_0{}", self.key, i));
+                let key = (i > missing).then(|| (i, self.key_for(i)));
+                std::iter::once(padded).chain(key)
+            })
+            .chain(
+                self.env_keys()
+                    .filter(|(i, key)| *i > missing || *key != self.key_for(*i)),
+            )
+            .collect();
+        rest.sort();
+        rest.dedup();
+        for (i, key) in rest {
+            if layer.lookup(&key)?.is_none() {
+                continue;
+            }
+            if key != self.key_for(i) {
+                anyhow::bail!(
+                    "Invalid ENV {}: indices of {}_* cannot have leading zeros",
+                    key,
+                    self.key
+                );
+            }
+            if self.max == Some(missing) {
+                anyhow::bail!(
+                    "Too many ENV {}_*: {} is set but at most {} items are allowed",
+                    self.key,
+                    key,
+                    missing
+                );
+            }
+            anyhow::bail!(
+                "Gap in ENV {}_*: {} is missing but {} is set",
+                self.key,
+                self.key_for(missing),
+                key
+            );
+        }
+        Ok(res)
+    }
+
+    /// Names of items which are set in the process environment with their indices,
+    /// including those with leading zeros.
+    fn env_keys(&self) -> impl Iterator<Item = (usize, String)> + '_ {
+        std::env::vars_os().filter_map(|(key, _)| {
+            let key = key.into_string().ok()?;
+            let index = key.strip_prefix(self.key)?.strip_prefix('_')?;
+            if !index.bytes().all(|x| x.is_ascii_digit()) {
+                return None;
+            }
+            Some((index.parse::<usize>().ok()?, key))
+        })
+    }
+
+    /// Indices of items which are set in the process environment,
+    /// excluding those with leading zeros.
+    fn env_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.env_keys()
+            .filter(|(i, key)| *key == self.key_for(*i))
+            .map(|(i, _)| i)
+    }
+}
+
+impl<T: Debug> Flag for IndexedList<T> {
+    fn key(&self) -> &'static str {
+        self.key
+    }
+
+    /// Names of items which are set in the process environment.
+    fn keys(&self) -> Vec<String> {
+        let mut indices: Vec<usize> = self.env_indices().collect();
+        indices.sort();
+        indices.into_iter().map(|i| self.key_for(i)).collect()
+    }
+
+    fn key_templates(&self) -> Vec<String> {
+        vec![format!("{}_{{}}", self.key)]
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn has_default(&self) -> bool {
+        false
+    }
+
+    fn diagnose(&self) -> Diagnosis {
+        let res = self.fetch_from_with_source(&Installed).map(|items| {
+            let source = items.first()?.1.clone();
+            let values: Vec<T> = items.into_iter().map(|(x, _)| x).collect();
+            Some((values, source))
+        });
+        Diagnosis::new(&self.meta, self.key, res)
+    }
+
+    fn validate_from(&self, layer: &dyn Layer) -> anyhow::Result<()> {
+        self.fetch_from(layer).map(|_| ())
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Indexed
+    }
+}
@@ -0,0 +1,103 @@
+use crate::Layer;
+
+/// How deep variables can refer to each other.
+const MAX_DEPTH: usize = 16;
+
+/// Expands `${VAR}` and `${VAR:-default}` in `val`, the value of `key`,
+/// with variables looked up from `layer`.
+///
+/// *   `$$` is a literal `$`, and so is `$` followed by anything but `{`.
+/// *   Unset variables expand to empty strings,
+///     while defaults apply to both unset and empty variables.
+/// *   Values of variables, as well as defaults, are expanded recursively.
+pub(crate) fn interpolate<L: Layer + ?Sized>(
+    layer: &L,
+    key: &str,
+    val: &str,
+) -> anyhow::Result<String> {
+    let mut stack = vec![key.to_string()];
+    expand(layer, val, &mut stack)
+}
+
+fn expand<L: Layer + ?Sized>(
+    layer: &L,
+    val: &str,
+    stack: &mut Vec<String>,
+) -> anyhow::Result<String> {
+    let mut res = String::new();
+    let mut rest = val;
+    while let Some(i) = rest.find('$') {
+        res.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        if let Some(x) = rest.strip_prefix('$') {
+            res.push('$');
+            rest = x;
+            continue;
+        }
+        let Some(x) = rest.strip_prefix('{') else {
+            res.push('$');
+            continue;
+        };
+        let Some(end) = closing_brace(x) else {
+            anyhow::bail!("Unterminated \"${{\" in ENV {}", stack[0]);
+        };
+        let (expr, x) = (&x[..end], &x[end + 1..]);
+        rest = x;
+        let (name, default) = match expr.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expr, None),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!("Invalid variable \"{}\" in ENV {}", name, stack[0]);
+        }
+        match (lookup(layer, name, stack)?, default) {
+            (Some(value), None) => res.push_str(&value),
+            (Some(value), Some(_)) if !value.is_empty() => res.push_str(&value),
+            (_, Some(default)) => res.push_str(&expand(layer, default, stack)?),
+            (None, None) => (),
+        }
+    }
+    res.push_str(rest);
+    Ok(res)
+}
+
+fn lookup<L: Layer + ?Sized>(
+    layer: &L,
+    name: &str,
+    stack: &mut Vec<String>,
+) -> anyhow::Result<Option<String>> {
+    if let Some(i) = stack.iter().position(|x| x == name) {
+        let mut cycle = stack[i..].to_vec();
+        cycle.push(name.to_string());
+        anyhow::bail!("Cyclic interpolation among ENVs: {}", cycle.join(" -> "));
+    }
+    if stack.len() >= MAX_DEPTH {
+        anyhow::bail!(
+            "Too deep interpolation of ENV {}: {} -> {}",
+            stack[0],
+            stack.join(" -> "),
+            name
+        );
+    }
+    let Some(val) = layer.get(name)? else {
+        return Ok(None);
+    };
+    stack.push(name.to_string());
+    let res = expand(layer, &val, stack);
+    stack.pop();
+    res.map(Some)
+}
+
+/// The position of the `}` closing a `${`, whose content starts `x`.
+fn closing_brace(x: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in x.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => (),
+        }
+    }
+    None
+}
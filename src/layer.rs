@@ -0,0 +1,374 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+/// A source of values of env variables.
+///
+/// Layers can be stacked by tuples, where the former one takes precedence.
+/// For example, `(Env, dotenv)` looks up the process environment first and
+/// then falls back to a `.env` file.
+/// For longer chains, see `Resolver`.
+pub trait Layer {
+    /// Looks up the value of `key` as well as where it comes from.
+    fn lookup(&self, key: &str) -> anyhow::Result<Option<(String, Source)>>;
+
+    fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        Ok(self.lookup(key)?.map(|(x, _)| x))
+    }
+
+    /// Like `lookup()`, but keeps values which are not valid unicode if the layer can,
+    /// e.g., paths in the process environment.
+    fn lookup_os(&self, key: &str) -> anyhow::Result<Option<(OsString, Source)>> {
+        Ok(self.lookup(key)?.map(|(x, source)| (x.into(), source)))
+    }
+
+    /// Whether the layer at address `layer` is this layer or one of those it looks up.
+    #[doc(hidden)]
+    fn contains(&self, layer: *const ()) -> bool {
+        std::ptr::eq(self as *const Self as *const (), layer)
+    }
+}
+
+/// Where the value of a flag comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The process environment, with the name of the env variable.
+    Env { key: String },
+    /// A command-line argument, e.g., `--port`.
+    Arg { arg: String },
+    /// A file, e.g., a `.env` file or a config file, with the key in it.
+    File { path: PathBuf, key: String },
+    /// The default value, and the profile if it is specific to a profile.
+    Default { profile: Option<String> },
+    /// A runtime override by `set_override()`.
+    Override,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Env { key } => write!(f, "env {}", key),
+            Source::Arg { arg } => write!(f, "argument {}", arg),
+            Source::File { path, key } => write!(f, "{} in {}", key, path.display()),
+            Source::Default { profile: None } => write!(f, "default"),
+            Source::Default {
+                profile: Some(profile),
+            } => write!(f, "default of profile {}", profile),
+            Source::Override => write!(f, "override"),
+        }
+    }
+}
+
+/// The process environment.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Env;
+
+impl Layer for Env {
+    fn lookup(&self, key: &str) -> anyhow::Result<Option<(String, Source)>> {
+        match std::env::var(key) {
+            Ok(x) => Ok(Some((
+                x,
+                Source::Env {
+                    key: key.to_string(),
+                },
+            ))),
+            Err(std::env::VarError::NotPresent) => Ok(None),
+            Err(std::env::VarError::NotUnicode(e)) => {
+                anyhow::bail!("Fail to fetch ENV {}: {:?}", key, e);
+            }
+        }
+    }
+
+    fn lookup_os(&self, key: &str) -> anyhow::Result<Option<(OsString, Source)>> {
+        let res = std::env::var_os(key).map(|x| {
+            let source = Source::Env {
+                key: key.to_string(),
+            };
+            (x, source)
+        });
+        Ok(res)
+    }
+}
+
+impl<L: Layer + ?Sized> Layer for &L {
+    fn lookup(&self, key: &str) -> anyhow::Result<Option<(String, Source)>> {
+        (**self).lookup(key)
+    }
+
+    fn lookup_os(&self, key: &str) -> anyhow::Result<Option<(OsString, Source)>> {
+        (**self).lookup_os(key)
+    }
+
+    fn contains(&self, layer: *const ()) -> bool {
+        (**self).contains(layer)
+    }
+}
+
+impl<L: Layer + ?Sized> Layer for Arc<L> {
+    fn lookup(&self, key: &str) -> anyhow::Result<Option<(String, Source)>> {
+        (**self).lookup(key)
+    }
+
+    fn lookup_os(&self, key: &str) -> anyhow::Result<Option<(OsString, Source)>> {
+        (**self).lookup_os(key)
+    }
+
+    fn contains(&self, layer: *const ()) -> bool {
+        (**self).contains(layer)
+    }
+}
+
+impl<A: Layer, B: Layer> Layer for (A, B) {
+    fn lookup(&self, key: &str) -> anyhow::Result<Option<(String, Source)>> {
+        match self.0.lookup(key)? {
+            Some(x) => Ok(Some(x)),
+            None => self.1.lookup(key),
+        }
+    }
+
+    fn lookup_os(&self, key: &str) -> anyhow::Result<Option<(OsString, Source)>> {
+        match self.0.lookup_os(key)? {
+            Some(x) => Ok(Some(x)),
+            None => self.1.lookup_os(key),
+        }
+    }
+
+    fn contains(&self, layer: *const ()) -> bool {
+        self.0.contains(layer) || self.1.contains(layer)
+    }
+}
+
+/// Values given by command-line arguments in form of `--key=value`.
+///
+/// Keys are matched against names of env variables case-insensitively,
+/// with `-` and `_` regarded the same.
+/// So `--metrics-port=9090` gives `METRICS_PORT`.
+/// Other arguments are ignored, and so is anything after `--`.
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    /// Normalized keys to values and the keys as they are.
+    vars: BTreeMap<String, (String, String)>,
+}
+
+impl Args {
+    /// Collects arguments of this process.
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::args_os()
+                .skip(1)
+                .filter_map(|x| x.into_string().ok()),
+        )
+    }
+
+    pub fn new<I, S>(args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut vars = BTreeMap::new();
+        for arg in args {
+            let arg = arg.as_ref();
+            if arg == "--" {
+                break;
+            }
+            let Some((key, value)) = arg.strip_prefix("--").and_then(|x| x.split_once('=')) else {
+                continue;
+            };
+            vars.insert(
+                normalize_arg(key),
+                (value.to_string(), format!("--{}", key)),
+            );
+        }
+        Self { vars }
+    }
+}
+
+impl Layer for Args {
+    fn lookup(&self, key: &str) -> anyhow::Result<Option<(String, Source)>> {
+        let res = self
+            .vars
+            .get(&normalize_arg(key))
+            .map(|(value, arg)| (value.clone(), Source::Arg { arg: arg.clone() }));
+        Ok(res)
+    }
+}
+
+fn normalize_arg(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c == '-' {
+                '_'
+            } else {
+                c.to_ascii_uppercase()
+            }
+        })
+        .collect()
+}
+
+/// An ordered chain of layers, where former layers take precedence.
+///
+/// Once installed, it replaces the process environment as what flags fetch from.
+///
+/// ```no_run
+/// # #[cfg(feature = "toml")]
+/// # {
+/// use rs_envflag::{Args, ConfigFile, Env, Resolver};
+///
+/// Resolver::new()
+///     .layer(Args::from_env())
+///     .layer(Env)
+///     .layer(ConfigFile::load("app.toml").unwrap())
+///     .install();
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Resolver {
+    layers: Vec<Box<dyn Layer + Send + Sync>>,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn layer(mut self, layer: impl Layer + Send + Sync + 'static) -> Self {
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    /// Makes `fetch()` of all flags go through this resolver.
+    pub fn install(self) {
+        *INSTALLED.write().unwrap() = Some(Arc::new(self));
+        crate::notify();
+    }
+
+    /// Makes `fetch()` of all flags go back to the process environment.
+    pub fn uninstall() {
+        *INSTALLED.write().unwrap() = None;
+        crate::notify();
+    }
+}
+
+impl Layer for Resolver {
+    fn lookup(&self, key: &str) -> anyhow::Result<Option<(String, Source)>> {
+        for layer in self.layers.iter() {
+            if let Some(x) = layer.lookup(key)? {
+                return Ok(Some(x));
+            }
+        }
+        Ok(None)
+    }
+
+    fn lookup_os(&self, key: &str) -> anyhow::Result<Option<(OsString, Source)>> {
+        for layer in self.layers.iter() {
+            if let Some(x) = layer.lookup_os(key)? {
+                return Ok(Some(x));
+            }
+        }
+        Ok(None)
+    }
+
+    fn contains(&self, layer: *const ()) -> bool {
+        self.layers.iter().any(|x| x.contains(layer))
+    }
+}
+
+static INSTALLED: RwLock<Option<Arc<Resolver>>> = RwLock::new(None);
+
+/// Runtime overrides, which take precedence over everything else.
+static OVERRIDES: RwLock<BTreeMap<&'static str, String>> = RwLock::new(BTreeMap::new());
+
+pub(crate) fn set_override(key: &'static str, value: String) {
+    OVERRIDES.write().unwrap().insert(key, value);
+    crate::notify();
+}
+
+pub(crate) fn clear_override(key: &str) {
+    let removed = OVERRIDES.write().unwrap().remove(key);
+    if removed.is_some() {
+        crate::notify();
+    }
+}
+
+/// What `fetch()` fetches from:
+/// the override if any, and then the installed resolver if any,
+/// or the process environment.
+pub(crate) struct Installed;
+
+thread_local! {
+    /// Layers which default values are being computed from on this thread, the innermost last.
+    static COMPUTING: RefCell<Vec<*const (dyn Layer + 'static)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// While it lives, `Installed` looks up `layer` on this thread,
+/// so flags fetched by `default_fn` come from the layer given to `fetch_from()`.
+pub(crate) struct ComputingGuard<'a>(PhantomData<&'a ()>);
+
+impl<'a> ComputingGuard<'a> {
+    pub(crate) fn enter(layer: &'a (dyn Layer + 'a)) -> Self {
+        let layer: *const (dyn Layer + 'a) = layer;
+        // SAFETY: the pointer is popped when the guard drops, which is within `'a`.
+        let layer: *const (dyn Layer + 'static) = unsafe { std::mem::transmute(layer) };
+        COMPUTING.with_borrow_mut(|x| x.push(layer));
+        ComputingGuard(PhantomData)
+    }
+}
+
+impl Drop for ComputingGuard<'_> {
+    fn drop(&mut self) {
+        COMPUTING.with_borrow_mut(|x| x.pop());
+    }
+}
+
+/// Calls `f` with the innermost layer being computed from, if any.
+/// Meanwhile, it is taken off, so `Installed` in the layer looks up the outer one.
+fn with_computing<R>(f: impl FnOnce(&dyn Layer) -> R) -> Option<R> {
+    struct Restore(*const (dyn Layer + 'static));
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            COMPUTING.with_borrow_mut(|x| x.push(self.0));
+        }
+    }
+    let layer = Restore(COMPUTING.with_borrow_mut(|x| x.pop())?);
+    // SAFETY: the guard which pushed the layer is still alive, since it is restored before returning.
+    Some(f(unsafe { &*layer.0 }))
+}
+
+impl Layer for Installed {
+    fn lookup(&self, key: &str) -> anyhow::Result<Option<(String, Source)>> {
+        if let Some(res) = with_computing(|layer| layer.lookup(key)) {
+            return res;
+        }
+        if let Some(value) = OVERRIDES.read().unwrap().get(key) {
+            return Ok(Some((value.clone(), Source::Override)));
+        }
+        let installed = INSTALLED.read().unwrap().clone();
+        match installed {
+            Some(resolver) => resolver.lookup(key),
+            None => Env.lookup(key),
+        }
+    }
+
+    fn lookup_os(&self, key: &str) -> anyhow::Result<Option<(OsString, Source)>> {
+        if let Some(res) = with_computing(|layer| layer.lookup_os(key)) {
+            return res;
+        }
+        if let Some(value) = OVERRIDES.read().unwrap().get(key) {
+            return Ok(Some((value.into(), Source::Override)));
+        }
+        let installed = INSTALLED.read().unwrap().clone();
+        match installed {
+            Some(resolver) => resolver.lookup_os(key),
+            None => Env.lookup_os(key),
+        }
+    }
+
+    fn contains(&self, layer: *const ()) -> bool {
+        let installed = INSTALLED.read().unwrap().clone();
+        installed.is_some_and(|x| x.contains(layer))
+    }
+}
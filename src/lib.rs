@@ -1,22 +1,349 @@
 #![doc = include_str!("../README.md")]
 
+use anyhow::Context;
+use std::cell::RefCell;
+use std::fmt::Debug;
+
+#[cfg(any(feature = "json", feature = "toml"))]
+mod config_file;
+pub mod diagnostics;
+mod dotenv;
+pub mod generate;
+mod indexed_list;
+mod interpolate;
+mod layer;
+mod path_list;
+mod prefix_map;
+mod profile;
+pub mod reload;
+mod rollout;
+pub mod strict;
+mod subscribe;
+mod template;
+mod variants;
+#[cfg(any(feature = "json", feature = "toml"))]
+pub use config_file::*;
+pub use diagnostics::Diagnosis;
+pub use dotenv::*;
+pub use indexed_list::*;
+pub use layer::*;
+pub use path_list::*;
+pub use prefix_map::*;
+pub use profile::*;
+pub use rollout::*;
+pub use subscribe::*;
+pub use template::*;
+pub use variants::*;
+
+/// A flag which is absent unless its env variable is set.
+///
+/// Fields may be added in minor versions, so it is built by `new()` and
+/// then fields are assigned.
+#[non_exhaustive]
 pub struct EnvFlag<T> {
     pub key: &'static str,
+    /// Names tried in order when `key` is absent, e.g., a platform-standard one.
+    pub fallbacks: &'static [&'static str],
     pub parser: fn(key: &str, val: &str) -> anyhow::Result<T>,
+    /// Whether the value can be read from the file named by `<key>_FILE`,
+    /// e.g., a secret mounted by Docker or Kubernetes.
+    pub allow_file: bool,
+    /// Whether `${VAR}` in values is expanded before parsing.
+    pub interpolate: bool,
+    /// Whether leading and trailing whitespaces of values are trimmed.
+    pub trim: bool,
+    pub empty: Empty,
+    pub meta: Meta,
+}
+
+/// How empty values, e.g., `FOO=`, are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Empty {
+    /// As if the flag is absent, so it falls back to the default if any.
+    Unset,
+    /// Fails.
+    Error,
+    /// Handed to the parser as it is.
+    Value,
+}
+
+/// Metadata of a flag which does not affect how it is fetched.
+///
+/// Fields may be added in minor versions, so it is built from `Meta::DEFAULT`.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct Meta {
+    /// Keys of flags which the default value is computed from.
+    pub depends_on: &'static [&'static str],
+    /// Values of secret flags are redacted in diagnostics.
+    pub secret: bool,
+    /// Doc comments of the flag.
+    pub doc: &'static str,
+    /// The type of values, as it is written in the definition.
+    pub type_name: &'static str,
+    /// The default value in the syntax of env variables, if it is known
+    /// without computing.
+    pub default: Option<&'static str>,
+    /// Accepted values as per `variants`, or names of variants of `Variants` flags
+    /// as per their defaults.
+    pub variants: &'static [&'static str],
+}
+
+impl Meta {
+    pub const DEFAULT: Meta = Meta {
+        depends_on: &[],
+        secret: false,
+        doc: "",
+        type_name: "",
+        default: None,
+        variants: &[],
+    };
+
+    /// Lines of doc comments, without the leading space of each line.
+    pub fn doc_lines(&self) -> impl Iterator<Item = &'static str> {
+        let doc = self.doc;
+        doc.lines().map(|x| x.strip_prefix(' ').unwrap_or(x))
+    }
+}
+
+/// Common interface of all kinds of flags, regardless of their value types.
+/// It is convenient to collect flags of an application by `&[&dyn Flag]`.
+pub trait Flag: Sync {
+    fn key(&self) -> &'static str;
+    /// Names of all env variables the flag reads, starting with `key()`.
+    fn keys(&self) -> Vec<String>;
+    /// Names of env variables which the flag may read, including fallbacks and
+    /// `_FILE` forms, where `{}` stands for indices of templated and indexed flags
+    /// and names of map flags.
+    fn key_templates(&self) -> Vec<String> {
+        self.keys()
+    }
+    fn meta(&self) -> &Meta;
+    /// Whether the flag falls back to a default value when it is absent.
+    fn has_default(&self) -> bool;
+    /// Whether fetching the flag fails when it is absent.
+    fn required(&self) -> bool {
+        false
+    }
+    /// Fetches the flag and describes the result.
+    fn diagnose(&self) -> Diagnosis;
+    /// Fetches the flag from `layer` but drops the value,
+    /// to tell whether `layer` is valid for the flag.
+    fn validate_from(&self, layer: &dyn Layer) -> anyhow::Result<()>;
+    /// How the flag maps to env variables.
+    fn kind(&self) -> Kind {
+        Kind::Single
+    }
+}
+
+/// How a flag maps to env variables, which generators of documents follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// One variable named `key()`.
+    Single,
+    /// One variable named `key()`, of paths separated like `PATH`.
+    PathList,
+    /// Variables named by replacing `{}` in `key()`, e.g., `SHARD_3_HOST`.
+    Templated,
+    /// Variables named `key()` followed by anything, e.g., `FEATURE_SEARCH`.
+    Prefixed,
+    /// Variables named `<key()>_0`, `<key()>_1`, ...
+    Indexed,
+}
+
+/// What a flag is declared as, e.g., `BTreeMap<String, T>` for `PrefixMap<T>`.
+/// The macro checks declared types by it.
+#[doc(hidden)]
+pub trait Declared {
+    type Declared;
 }
 
 impl<T> EnvFlag<T> {
+    /// A flag without fallbacks, policies or metadata.
+    pub const fn new(key: &'static str, parser: fn(&str, &str) -> anyhow::Result<T>) -> Self {
+        Self {
+            key,
+            fallbacks: &[],
+            parser,
+            allow_file: false,
+            interpolate: false,
+            trim: false,
+            empty: Empty::Value,
+            meta: Meta::DEFAULT,
+        }
+    }
+
+    pub const fn key(&self) -> &'static str {
+        self.key
+    }
+
     pub fn fetch(&'static self) -> anyhow::Result<Option<T>> {
-        match std::env::var(self.key) {
-            Ok(x) => Some((self.parser)(self.key, &x)).transpose(),
-            Err(std::env::VarError::NotPresent) => Ok(None),
-            Err(std::env::VarError::NotUnicode(e)) => {
-                anyhow::bail!("Fail to fetch ENV {}: {:?}", self.key, e);
+        self.fetch_from(&Installed)
+    }
+
+    pub fn fetch_from<L: Layer + ?Sized>(&self, layer: &L) -> anyhow::Result<Option<T>> {
+        Ok(self.fetch_from_with_source(layer)?.map(|(x, _)| x))
+    }
+
+    pub fn fetch_with_source(&'static self) -> anyhow::Result<Option<(T, Source)>> {
+        self.fetch_from_with_source(&Installed)
+    }
+
+    pub fn fetch_from_with_source<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+    ) -> anyhow::Result<Option<(T, Source)>> {
+        self.fetch_renamed(layer, &|key| key.to_string())
+    }
+
+    /// Fetches with names of the flag mapped by `rename`, e.g., for templated flags.
+    pub(crate) fn fetch_renamed<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<Option<(T, Source)>> {
+        let Some((key, val, source)) = self.lookup(layer, rename)? else {
+            return Ok(None);
+        };
+        Ok(self.parse(layer, &key, val)?.map(|x| (x, source)))
+    }
+
+    /// Parses a value of `key`, one of names of the flag, looked up from `layer`,
+    /// applying policies of the flag.
+    fn parse<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        key: &str,
+        mut val: String,
+    ) -> anyhow::Result<Option<T>> {
+        if self.interpolate {
+            val = interpolate::interpolate(layer, key, &val)?;
+        }
+        if self.trim {
+            val = val.trim().to_string();
+        }
+        if val.is_empty() {
+            match self.empty {
+                Empty::Unset => return Ok(None),
+                Empty::Error => anyhow::bail!("Empty ENV \"{}\"", key),
+                Empty::Value => (),
             }
         }
+        (self.parser)(key, &val).map(Some)
+    }
+
+    /// Looks up names of the flag in order, where the first present one wins,
+    /// and returns it along with the value.
+    fn lookup<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<Option<(String, String, Source)>> {
+        for key in std::iter::once(&self.key).chain(self.fallbacks.iter()) {
+            let key = rename(key);
+            if let Some((val, source)) = self.lookup_name(layer, &key)? {
+                return Ok(Some((key, val, source)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn lookup_name<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        key: &str,
+    ) -> anyhow::Result<Option<(String, Source)>> {
+        let res = layer.lookup(key)?;
+        // Overrides take precedence over files too.
+        if !self.allow_file || matches!(res, Some((_, Source::Override))) {
+            return Ok(res);
+        }
+        let file_key = format!("{}_FILE", key);
+        let Some((path, _)) = layer.lookup(&file_key)? else {
+            return Ok(res);
+        };
+        if res.is_some() {
+            anyhow::bail!("Both ENV {} and {} are set", key, file_key);
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Fail to read {} from ENV {}", path, file_key))?;
+        let val = content
+            .strip_suffix('\n')
+            .map(|x| x.strip_suffix('\r').unwrap_or(x))
+            .unwrap_or(&content);
+        let source = Source::File {
+            path: path.into(),
+            key: key.to_string(),
+        };
+        Ok(Some((val.to_string(), source)))
+    }
+
+    /// Overrides the value process-widely, taking precedence over all layers.
+    /// The value is given in its textual form, which must be parsed successfully.
+    pub fn set_override(&self, value: impl ToString) -> anyhow::Result<()> {
+        let value = value.to_string();
+        self.parse(&Installed, self.key, value.clone())?;
+        layer::set_override(self.key, value);
+        Ok(())
+    }
+
+    pub fn clear_override(&self) {
+        layer::clear_override(self.key)
+    }
+
+    /// Calls `callback` with old and new values whenever the value changes.
+    /// See `notify()` for when it is checked.
+    pub fn subscribe(
+        &'static self,
+        callback: impl FnMut(&Option<T>, &Option<T>) + Send + 'static,
+    ) -> Subscription
+    where
+        T: PartialEq + Send + 'static,
+    {
+        subscribe::subscribe(move || self.fetch(), callback)
+    }
+}
+
+impl<T: Debug> Flag for EnvFlag<T> {
+    fn key(&self) -> &'static str {
+        self.key
+    }
+
+    fn keys(&self) -> Vec<String> {
+        let mut res = vec![];
+        for key in std::iter::once(&self.key).chain(self.fallbacks.iter()) {
+            res.push(key.to_string());
+            if self.allow_file {
+                res.push(format!("{}_FILE", key));
+            }
+        }
+        res
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn has_default(&self) -> bool {
+        false
+    }
+
+    fn diagnose(&self) -> Diagnosis {
+        Diagnosis::new(
+            &self.meta,
+            self.key,
+            self.fetch_from_with_source(&Installed),
+        )
+    }
+
+    fn validate_from(&self, layer: &dyn Layer) -> anyhow::Result<()> {
+        self.fetch_from(layer).map(|_| ())
     }
 }
 
+/// Fields may be added in minor versions, so it is built by `new()` and
+/// then fields are assigned.
+#[non_exhaustive]
 pub struct EnvFlagWithDefault<T, DefaultT>
 where
     T: Clone,
@@ -24,6 +351,8 @@ where
 {
     pub env: EnvFlag<T>,
     pub default: &'static DefaultT,
+    /// Defaults overriding `default` under specific profiles.
+    pub profiles: &'static [(&'static str, &'static DefaultT)],
 }
 
 impl<T, DefaultT> EnvFlagWithDefault<T, DefaultT>
@@ -31,10 +360,434 @@ where
     T: Clone,
     DefaultT: ToOwned<Owned = T> + 'static + ?Sized,
 {
+    /// A flag without defaults specific to profiles.
+    pub const fn new(env: EnvFlag<T>, default: &'static DefaultT) -> Self {
+        Self {
+            env,
+            default,
+            profiles: &[],
+        }
+    }
+
+    pub const fn key(&self) -> &'static str {
+        self.env.key
+    }
+
+    pub fn fetch(&'static self) -> anyhow::Result<T> {
+        self.fetch_from(&Installed)
+    }
+
+    pub fn fetch_from<L: Layer + ?Sized>(&self, layer: &L) -> anyhow::Result<T> {
+        self.fetch_from_with_source(layer).map(|(x, _)| x)
+    }
+
+    pub fn fetch_with_source(&'static self) -> anyhow::Result<(T, Source)> {
+        self.fetch_from_with_source(&Installed)
+    }
+
+    pub fn fetch_from_with_source<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+    ) -> anyhow::Result<(T, Source)> {
+        self.fetch_renamed(layer, &|key| key.to_string())
+    }
+
+    pub(crate) fn fetch_renamed<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<(T, Source)> {
+        match self.env.fetch_renamed(layer, rename)? {
+            Some(x) => Ok(x),
+            None => {
+                let (default, profile) = self.profile_default(layer)?;
+                Ok((default.to_owned(), Source::Default { profile }))
+            }
+        }
+    }
+
+    /// Calls `callback` with old and new values whenever the value changes.
+    /// See `notify()` for when it is checked.
+    pub fn subscribe(&'static self, callback: impl FnMut(&T, &T) + Send + 'static) -> Subscription
+    where
+        T: PartialEq + Send + 'static,
+        DefaultT: Sync,
+    {
+        subscribe::subscribe(move || self.fetch(), callback)
+    }
+
+    /// Overrides the value process-widely, taking precedence over all layers.
+    /// The value is given in its textual form, which must be parsed successfully.
+    pub fn set_override(&self, value: impl ToString) -> anyhow::Result<()> {
+        self.env.set_override(value)
+    }
+
+    pub fn clear_override(&self) {
+        self.env.clear_override()
+    }
+
+    /// The default under the profile selected in `layer`.
+    fn profile_default<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+    ) -> anyhow::Result<(&'static DefaultT, Option<String>)> {
+        if self.profiles.is_empty() {
+            return Ok((self.default, None));
+        }
+        let Some(profile) = active_profile_from(layer)? else {
+            return Ok((self.default, None));
+        };
+        let res = match self.profiles.iter().find(|(name, _)| *name == profile) {
+            Some((_, default)) => (*default, Some(profile)),
+            None => (self.default, None),
+        };
+        Ok(res)
+    }
+}
+
+impl<T, DefaultT> Flag for EnvFlagWithDefault<T, DefaultT>
+where
+    T: Clone + Debug,
+    DefaultT: ToOwned<Owned = T> + Sync + 'static + ?Sized,
+{
+    fn key(&self) -> &'static str {
+        self.env.key
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.env.keys()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.env.meta
+    }
+
+    fn has_default(&self) -> bool {
+        true
+    }
+
+    fn diagnose(&self) -> Diagnosis {
+        let res = self.fetch_from_with_source(&Installed).map(Some);
+        Diagnosis::new(&self.env.meta, self.env.key, res)
+    }
+
+    fn validate_from(&self, layer: &dyn Layer) -> anyhow::Result<()> {
+        self.fetch_from(layer).map(|_| ())
+    }
+}
+
+pub struct EnvFlagWithDefaultStr<T> {
+    pub env: EnvFlag<T>,
+    pub default: &'static str,
+}
+
+impl<T> EnvFlagWithDefaultStr<T> {
+    pub const fn key(&self) -> &'static str {
+        self.env.key
+    }
+
+    pub fn fetch(&'static self) -> anyhow::Result<T> {
+        self.fetch_from(&Installed)
+    }
+
+    pub fn fetch_from<L: Layer + ?Sized>(&self, layer: &L) -> anyhow::Result<T> {
+        self.fetch_from_with_source(layer).map(|(x, _)| x)
+    }
+
+    pub fn fetch_with_source(&'static self) -> anyhow::Result<(T, Source)> {
+        self.fetch_from_with_source(&Installed)
+    }
+
+    pub fn fetch_from_with_source<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+    ) -> anyhow::Result<(T, Source)> {
+        self.fetch_renamed(layer, &|key| key.to_string())
+    }
+
+    pub(crate) fn fetch_renamed<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<(T, Source)> {
+        match self.env.fetch_renamed(layer, rename)? {
+            Some(x) => Ok(x),
+            None => Ok((self.parse_default()?, Source::Default { profile: None })),
+        }
+    }
+
+    /// Calls `callback` with old and new values whenever the value changes.
+    /// See `notify()` for when it is checked.
+    pub fn subscribe(&'static self, callback: impl FnMut(&T, &T) + Send + 'static) -> Subscription
+    where
+        T: PartialEq + Send + 'static,
+    {
+        subscribe::subscribe(move || self.fetch(), callback)
+    }
+
+    /// Overrides the value process-widely, taking precedence over all layers.
+    /// The value is given in its textual form, which must be parsed successfully.
+    pub fn set_override(&self, value: impl ToString) -> anyhow::Result<()> {
+        self.env.set_override(value)
+    }
+
+    pub fn clear_override(&self) {
+        self.env.clear_override()
+    }
+
+    /// Parses the default string as well as the env value, if any, without
+    /// fetching. It is convenient to call it at startup to fail fast.
+    pub fn validate(&'static self) -> anyhow::Result<()> {
+        self.env.fetch()?;
+        self.parse_default()?;
+        Ok(())
+    }
+
+    fn parse_default(&self) -> anyhow::Result<T> {
+        (self.env.parser)(self.env.key, self.default)
+            .with_context(|| format!("Invalid default of ENV \"{}\"", self.env.key))
+    }
+}
+
+impl<T: Debug> Flag for EnvFlagWithDefaultStr<T> {
+    fn key(&self) -> &'static str {
+        self.env.key
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.env.keys()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.env.meta
+    }
+
+    fn has_default(&self) -> bool {
+        true
+    }
+
+    fn diagnose(&self) -> Diagnosis {
+        let res = self.fetch_from_with_source(&Installed).map(Some);
+        Diagnosis::new(&self.env.meta, self.env.key, res)
+    }
+
+    fn validate_from(&self, layer: &dyn Layer) -> anyhow::Result<()> {
+        self.fetch_from(layer).map(|_| ())
+    }
+}
+
+pub struct EnvFlagWithDefaultFn<T> {
+    pub env: EnvFlag<T>,
+    pub default: fn() -> anyhow::Result<T>,
+}
+
+impl<T> EnvFlagWithDefaultFn<T> {
+    pub const fn key(&self) -> &'static str {
+        self.env.key
+    }
+
+    pub fn fetch(&'static self) -> anyhow::Result<T> {
+        self.fetch_from(&Installed)
+    }
+
+    pub fn fetch_from<L: Layer + ?Sized>(&self, layer: &L) -> anyhow::Result<T> {
+        self.fetch_from_with_source(layer).map(|(x, _)| x)
+    }
+
+    pub fn fetch_with_source(&'static self) -> anyhow::Result<(T, Source)> {
+        self.fetch_from_with_source(&Installed)
+    }
+
+    pub fn fetch_from_with_source<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+    ) -> anyhow::Result<(T, Source)> {
+        self.fetch_renamed(layer, &|key| key.to_string())
+    }
+
+    pub(crate) fn fetch_renamed<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<(T, Source)> {
+        match self.env.fetch_renamed(layer, rename)? {
+            Some(x) => Ok(x),
+            None => {
+                let key = rename(self.env.key);
+                let _resolving = ResolvingGuard::enter(&key)?;
+                let _computing = ComputingGuard::enter(&layer);
+                let res = (self.default)()
+                    .with_context(|| format!("Fail to compute default of ENV \"{}\"", key))?;
+                Ok((res, Source::Default { profile: None }))
+            }
+        }
+    }
+
+    /// Overrides the value process-widely, taking precedence over all layers.
+    /// The value is given in its textual form, which must be parsed successfully.
+    pub fn set_override(&self, value: impl ToString) -> anyhow::Result<()> {
+        self.env.set_override(value)
+    }
+
+    pub fn clear_override(&self) {
+        self.env.clear_override()
+    }
+
+    /// Calls `callback` with old and new values whenever the value changes.
+    /// See `notify()` for when it is checked.
+    pub fn subscribe(&'static self, callback: impl FnMut(&T, &T) + Send + 'static) -> Subscription
+    where
+        T: PartialEq + Send + 'static,
+    {
+        subscribe::subscribe(move || self.fetch(), callback)
+    }
+}
+
+impl<T: Debug> Flag for EnvFlagWithDefaultFn<T> {
+    fn key(&self) -> &'static str {
+        self.env.key
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.env.keys()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.env.meta
+    }
+
+    fn has_default(&self) -> bool {
+        true
+    }
+
+    fn diagnose(&self) -> Diagnosis {
+        let res = self.fetch_from_with_source(&Installed).map(Some);
+        Diagnosis::new(&self.env.meta, self.env.key, res)
+    }
+
+    fn validate_from(&self, layer: &dyn Layer) -> anyhow::Result<()> {
+        self.fetch_from(layer).map(|_| ())
+    }
+}
+
+/// A flag which is neither an `Option` nor has a default, so it must be set.
+pub struct EnvFlagRequired<T> {
+    pub env: EnvFlag<T>,
+}
+
+impl<T> EnvFlagRequired<T> {
+    pub const fn key(&self) -> &'static str {
+        self.env.key
+    }
+
     pub fn fetch(&'static self) -> anyhow::Result<T> {
-        self.env
-            .fetch()
-            .map(|x| x.unwrap_or(self.default.to_owned()))
+        self.fetch_from(&Installed)
+    }
+
+    pub fn fetch_from<L: Layer + ?Sized>(&self, layer: &L) -> anyhow::Result<T> {
+        self.fetch_from_with_source(layer).map(|(x, _)| x)
+    }
+
+    pub fn fetch_with_source(&'static self) -> anyhow::Result<(T, Source)> {
+        self.fetch_from_with_source(&Installed)
+    }
+
+    pub fn fetch_from_with_source<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+    ) -> anyhow::Result<(T, Source)> {
+        self.fetch_renamed(layer, &|key| key.to_string())
+    }
+
+    pub(crate) fn fetch_renamed<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<(T, Source)> {
+        match self.env.fetch_renamed(layer, rename)? {
+            Some(x) => Ok(x),
+            None => anyhow::bail!("Missing ENV \"{}\"", rename(self.env.key)),
+        }
+    }
+
+    /// Overrides the value process-widely, taking precedence over all layers.
+    /// The value is given in its textual form, which must be parsed successfully.
+    pub fn set_override(&self, value: impl ToString) -> anyhow::Result<()> {
+        self.env.set_override(value)
+    }
+
+    pub fn clear_override(&self) {
+        self.env.clear_override()
+    }
+
+    /// Calls `callback` with old and new values whenever the value changes.
+    /// See `notify()` for when it is checked.
+    pub fn subscribe(&'static self, callback: impl FnMut(&T, &T) + Send + 'static) -> Subscription
+    where
+        T: PartialEq + Send + 'static,
+    {
+        subscribe::subscribe(move || self.fetch(), callback)
+    }
+}
+
+impl<T: Debug> Flag for EnvFlagRequired<T> {
+    fn key(&self) -> &'static str {
+        self.env.key
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.env.keys()
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.env.meta
+    }
+
+    fn has_default(&self) -> bool {
+        false
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+
+    fn diagnose(&self) -> Diagnosis {
+        let res = self.fetch_from_with_source(&Installed).map(Some);
+        Diagnosis::new(&self.env.meta, self.env.key, res)
+    }
+
+    fn validate_from(&self, layer: &dyn Layer) -> anyhow::Result<()> {
+        self.fetch_from(layer).map(|_| ())
+    }
+}
+
+thread_local! {
+    /// Names of flags whose default values are being computed on this thread,
+    /// where templated names are filled with indices.
+    static RESOLVING: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+struct ResolvingGuard;
+
+impl ResolvingGuard {
+    fn enter(key: &str) -> anyhow::Result<Self> {
+        RESOLVING.with_borrow_mut(|resolving| {
+            if let Some(pos) = resolving.iter().position(|x| x == key) {
+                let mut cycle = resolving[pos..].to_vec();
+                cycle.push(key.to_string());
+                anyhow::bail!("Cyclic dependency among ENVs: {}", cycle.join(" -> "));
+            }
+            resolving.push(key.to_string());
+            Ok(ResolvingGuard)
+        })
+    }
+}
+
+impl Drop for ResolvingGuard {
+    fn drop(&mut self) {
+        RESOLVING.with_borrow_mut(|resolving| {
+            resolving.pop();
+        });
     }
 }
 
@@ -0,0 +1,123 @@
+use crate::{Declared, Diagnosis, Flag, Installed, Kind, Layer, Meta, Source};
+use std::ffi::OsStr;
+use std::path::PathBuf;
+
+/// A list of paths separated like `PATH`, i.e., by `:`, or `;` on Windows.
+///
+/// Paths are split by `std::env::split_paths()`, so bytes which are not valid unicode are kept.
+/// A leading `~` of each path is expanded to the home directory.
+pub struct PathList {
+    pub key: &'static str,
+    pub empty_paths: EmptyPaths,
+    pub meta: Meta,
+}
+
+/// How empty components, e.g., in `/bin::/usr/bin`, are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyPaths {
+    /// Skipped.
+    Drop,
+    /// Fails.
+    Error,
+    /// Kept as empty paths.
+    Keep,
+}
+
+impl PathList {
+    pub const fn key(&self) -> &'static str {
+        self.key
+    }
+
+    /// Returns an empty list if the flag is absent.
+    pub fn fetch(&'static self) -> anyhow::Result<Vec<PathBuf>> {
+        self.fetch_from(&Installed)
+    }
+
+    pub fn fetch_from<L: Layer + ?Sized>(&self, layer: &L) -> anyhow::Result<Vec<PathBuf>> {
+        Ok(self
+            .fetch_from_with_source(layer)?
+            .map(|(x, _)| x)
+            .unwrap_or_default())
+    }
+
+    pub fn fetch_with_source(&'static self) -> anyhow::Result<Option<(Vec<PathBuf>, Source)>> {
+        self.fetch_from_with_source(&Installed)
+    }
+
+    pub fn fetch_from_with_source<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+    ) -> anyhow::Result<Option<(Vec<PathBuf>, Source)>> {
+        let Some((val, source)) = layer.lookup_os(self.key)? else {
+            return Ok(None);
+        };
+        Ok(Some((self.parse(&val)?, source)))
+    }
+
+    fn parse(&self, val: &OsStr) -> anyhow::Result<Vec<PathBuf>> {
+        let mut res = vec![];
+        for (i, path) in std::env::split_paths(val).enumerate() {
+            if path.as_os_str().is_empty() {
+                match self.empty_paths {
+                    EmptyPaths::Drop => continue,
+                    EmptyPaths::Error => {
+                        anyhow::bail!("Empty path at {} of ENV \"{}\"", i, self.key);
+                    }
+                    EmptyPaths::Keep => (),
+                }
+            }
+            res.push(expand_tilde(path));
+        }
+        Ok(res)
+    }
+}
+
+/// Expands `~` and `~/...`, but not `~user/...`.
+fn expand_tilde(path: PathBuf) -> PathBuf {
+    let Ok(rest) = path.strip_prefix("~") else {
+        return path;
+    };
+    match std::env::var_os("HOME") {
+        Some(home) if !home.is_empty() && rest.as_os_str().is_empty() => PathBuf::from(home),
+        Some(home) if !home.is_empty() => PathBuf::from(home).join(rest),
+        _ => path,
+    }
+}
+
+impl Declared for PathList {
+    type Declared = Vec<PathBuf>;
+}
+
+impl Flag for PathList {
+    fn key(&self) -> &'static str {
+        self.key
+    }
+
+    fn keys(&self) -> Vec<String> {
+        vec![self.key.to_string()]
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn has_default(&self) -> bool {
+        false
+    }
+
+    fn diagnose(&self) -> Diagnosis {
+        Diagnosis::new(
+            &self.meta,
+            self.key,
+            self.fetch_from_with_source(&Installed),
+        )
+    }
+
+    fn validate_from(&self, layer: &dyn Layer) -> anyhow::Result<()> {
+        self.fetch_from(layer).map(|_| ())
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::PathList
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{Declared, Diagnosis, Flag, Kind, Layer, Meta, Source};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+/// A flag collecting all env variables with a prefix, e.g., `FEATURE_*`,
+/// into a map from names without the prefix to parsed values.
+///
+/// Unlike other flags, it reads only the process environment,
+/// since layers cannot be enumerated.
+pub struct PrefixMap<T> {
+    pub prefix: &'static str,
+    pub parser: fn(key: &str, val: &str) -> anyhow::Result<T>,
+    pub meta: Meta,
+}
+
+impl<T> PrefixMap<T> {
+    pub const fn prefix(&self) -> &'static str {
+        self.prefix
+    }
+
+    /// Fails if any of the variables fails, with errors of all of them.
+    pub fn fetch(&self) -> anyhow::Result<BTreeMap<String, T>> {
+        let mut res = BTreeMap::new();
+        let mut errors = vec![];
+        for (name, x) in self.fetch_entries() {
+            match x {
+                Ok(x) => {
+                    res.insert(name, x);
+                }
+                Err(e) => errors.push(format!("{:#}", e)),
+            }
+        }
+        if !errors.is_empty() {
+            anyhow::bail!("{}", errors.join("; "));
+        }
+        Ok(res)
+    }
+
+    /// Fetches variables one by one, so that failures can be handled per entry.
+    pub fn fetch_entries(&self) -> BTreeMap<String, anyhow::Result<T>> {
+        self.vars()
+            .into_iter()
+            .map(|(key, val)| {
+                let name = key[self.prefix.len()..].to_string();
+                let res = val.and_then(|val| (self.parser)(&key, &val));
+                (name, res)
+            })
+            .collect()
+    }
+
+    /// Names and values of variables with the prefix,
+    /// excluding the one named exactly the prefix.
+    fn vars(&self) -> Vec<(String, anyhow::Result<String>)> {
+        std::env::vars_os()
+            .filter_map(|(key, val)| {
+                let key = key.into_string().ok()?;
+                if key.len() <= self.prefix.len() || !key.starts_with(self.prefix) {
+                    return None;
+                }
+                let val = val
+                    .into_string()
+                    .map_err(|e| anyhow::Error::msg(format!("Fail to fetch ENV {}: {:?}", key, e)));
+                Some((key, val))
+            })
+            .collect()
+    }
+}
+
+impl<T> Declared for PrefixMap<T> {
+    type Declared = BTreeMap<String, T>;
+}
+
+impl<T: Debug> Flag for PrefixMap<T> {
+    fn key(&self) -> &'static str {
+        self.prefix
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.vars().into_iter().map(|(key, _)| key).collect()
+    }
+
+    fn key_templates(&self) -> Vec<String> {
+        vec![format!("{}{{}}", self.prefix)]
+    }
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn has_default(&self) -> bool {
+        false
+    }
+
+    fn diagnose(&self) -> Diagnosis {
+        let source = Source::Env {
+            key: format!("{}*", self.prefix),
+        };
+        let res = self.fetch().map(|x| Some((x, source)));
+        Diagnosis::new(&self.meta, self.prefix, res)
+    }
+
+    /// Checks the process environment regardless of `layer`.
+    fn validate_from(&self, _layer: &dyn Layer) -> anyhow::Result<()> {
+        self.fetch().map(|_| ())
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Prefixed
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{Installed, Layer};
+use std::sync::RwLock;
+
+static PROFILE_KEY: RwLock<&'static str> = RwLock::new("APP_PROFILE");
+
+/// Changes the env variable selecting the active profile.
+/// By default, it is `APP_PROFILE`.
+pub fn set_profile_key(key: &'static str) {
+    *PROFILE_KEY.write().unwrap() = key;
+}
+
+pub fn profile_key() -> &'static str {
+    *PROFILE_KEY.read().unwrap()
+}
+
+/// Returns the active profile, or `None` when no profile is selected.
+/// It is looked up like flags, i.e., from the installed resolver if any.
+pub fn active_profile() -> anyhow::Result<Option<String>> {
+    active_profile_from(&Installed)
+}
+
+/// Returns the active profile selected in `layer`.
+pub fn active_profile_from<L: Layer + ?Sized>(layer: &L) -> anyhow::Result<Option<String>> {
+    match layer.get(profile_key())? {
+        Some(x) if x.is_empty() => Ok(None),
+        x => Ok(x),
+    }
+}
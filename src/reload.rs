@@ -0,0 +1,223 @@
+//! Flag values which can be reloaded without restarting the process.
+//!
+//! ```no_run
+//! use rs_envflag::reload::Reloadable;
+//! use rs_envflag::{Env, Flag, Resolver};
+//! use rs_envflag_macros::*;
+//! use std::time::Duration;
+//!
+//! #[envflag(default=16)]
+//! const POOL_SIZE: i64;
+//!
+//! const FLAGS: &[&dyn Flag] = &[&POOL_SIZE];
+//!
+//! let store = Reloadable::open("app.env", FLAGS).unwrap();
+//! store.poll(Duration::from_secs(1));
+//! Resolver::new().layer(Env).layer(store).install();
+//! ```
+
+use crate::{notify, DotEnv, Flag, Installed, Layer, Source};
+use anyhow::Context;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+
+thread_local! {
+    /// Values being validated on this thread, with the address of the store they are for.
+    static STAGED: RefCell<Option<(usize, Arc<DotEnv>)>> = const { RefCell::new(None) };
+}
+
+/// A store of values backed by a `.env` file, which can be reloaded.
+///
+/// Reloading parses the file and validates all flags against it,
+/// and then swaps all values at once.
+/// If anything goes wrong, previous values are kept.
+///
+/// Once the store is a layer of the installed resolver,
+/// flags are validated through the resolver with new values in place of the store,
+/// so values overridden by other layers do not matter,
+/// and interpolation sees variables of other layers.
+pub struct Reloadable {
+    path: PathBuf,
+    flags: &'static [&'static dyn Flag],
+    current: RwLock<Arc<DotEnv>>,
+    /// The content of the file when it was loaded.
+    content: Mutex<Option<Vec<u8>>>,
+}
+
+impl Reloadable {
+    /// Loads the file for the first time, which must be valid for `flags`.
+    pub fn open(
+        path: impl AsRef<Path>,
+        flags: &'static [&'static dyn Flag],
+    ) -> anyhow::Result<Arc<Self>> {
+        let path = path.as_ref().to_path_buf();
+        let content = std::fs::read(&path).ok();
+        let dotenv = DotEnv::load(&path)?;
+        for flag in flags.iter() {
+            flag.validate_from(&dotenv)
+                .with_context(|| format!("Invalid {}", path.display()))?;
+        }
+        Ok(Arc::new(Self {
+            path,
+            flags,
+            current: RwLock::new(Arc::new(dotenv)),
+            content: Mutex::new(content),
+        }))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// A snapshot of current values.
+    pub fn snapshot(&self) -> Arc<DotEnv> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Reloads the file and notifies subscribers of changed flags.
+    /// On failures, previous values are kept and the error is returned.
+    pub fn reload(&self) -> anyhow::Result<()> {
+        let content = std::fs::read(&self.path).ok();
+        let dotenv = Arc::new(DotEnv::load(&self.path)?);
+        self.validate(&dotenv)
+            .with_context(|| format!("Invalid {}", self.path.display()))?;
+        *self.current.write().unwrap() = dotenv;
+        *self.content.lock().unwrap() = content;
+        notify();
+        Ok(())
+    }
+
+    /// Validates all flags with `dotenv` in place of current values.
+    fn validate(&self, dotenv: &Arc<DotEnv>) -> anyhow::Result<()> {
+        let address = self as *const Self as *const ();
+        if !Installed.contains(address) {
+            for flag in self.flags.iter() {
+                flag.validate_from(&**dotenv)?;
+            }
+            return Ok(());
+        }
+        STAGED.set(Some((address as usize, dotenv.clone())));
+        let _unstage = Unstage;
+        self.flags
+            .iter()
+            .try_for_each(|flag| flag.validate_from(&Installed))
+    }
+
+    /// Reloads the file only if its content changes.
+    /// Returns whether it is reloaded.
+    pub fn reload_if_changed(&self) -> anyhow::Result<bool> {
+        let content = std::fs::read(&self.path).ok();
+        if content == *self.content.lock().unwrap() {
+            return Ok(false);
+        }
+        self.reload()?;
+        Ok(true)
+    }
+
+    /// Spawns a thread reloading the file once it changes,
+    /// which is notified by the file system, e.g., by inotify on Linux.
+    /// Errors are printed to stderr.
+    ///
+    /// The directory of the file is watched,
+    /// so files replaced by renaming, as editors often do, are picked up as well.
+    /// The thread exits once the store is dropped.
+    #[cfg(feature = "watch")]
+    pub fn watch(self: &Arc<Self>) -> anyhow::Result<std::thread::JoinHandle<()>> {
+        use notify::Watcher;
+        use std::sync::mpsc::RecvTimeoutError;
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let dir = match self.path.parent() {
+            Some(x) if !x.as_os_str().is_empty() => x,
+            _ => Path::new("."),
+        };
+        let watcher = notify::recommended_watcher(tx)
+            .and_then(|mut x| x.watch(dir, notify::RecursiveMode::NonRecursive).map(|_| x))
+            .with_context(|| format!("Fail to watch {}", self.path.display()))?;
+        let store = Arc::downgrade(self);
+        let handle = std::thread::spawn(move || {
+            // Events stop once the watcher is dropped.
+            let _watcher = watcher;
+            loop {
+                // Wakes up now and then to tell whether the store is dropped.
+                let event = match rx.recv_timeout(Duration::from_secs(1)) {
+                    Ok(x) => Some(x),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                };
+                let Some(store) = Weak::upgrade(&store) else {
+                    return;
+                };
+                let res = match event {
+                    Some(Ok(_)) => store.reload_if_changed().map(|_| ()),
+                    Some(Err(e)) => Err(e.into()),
+                    None => Ok(()),
+                };
+                if let Err(e) = res {
+                    eprintln!("{:#}", e);
+                }
+            }
+        });
+        Ok(handle)
+    }
+
+    /// Spawns a thread checking the file for changes every `interval`,
+    /// for file systems which do not notify changes, e.g., some network ones.
+    /// Errors are printed to stderr.
+    ///
+    /// Changes are picked up within `interval`, and changes reverted within it are missed.
+    /// The thread exits once the store is dropped.
+    pub fn poll(self: &Arc<Self>, interval: Duration) -> std::thread::JoinHandle<()> {
+        let store = Arc::downgrade(self);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            let Some(store) = Weak::upgrade(&store) else {
+                return;
+            };
+            if let Err(e) = store.reload_if_changed() {
+                eprintln!("{:#}", e);
+            }
+        })
+    }
+
+    /// Spawns a thread reloading the file on every SIGHUP.
+    /// Errors are printed to stderr.
+    #[cfg(all(unix, feature = "signal"))]
+    pub fn reload_on_sighup(self: &Arc<Self>) -> std::io::Result<std::thread::JoinHandle<()>> {
+        let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGHUP])?;
+        let store = Arc::downgrade(self);
+        let handle = std::thread::spawn(move || {
+            for _ in signals.forever() {
+                let Some(store) = Weak::upgrade(&store) else {
+                    return;
+                };
+                if let Err(e) = store.reload() {
+                    eprintln!("{:#}", e);
+                }
+            }
+        });
+        Ok(handle)
+    }
+}
+
+/// Clears `STAGED` once dropped, even on panics.
+struct Unstage;
+
+impl Drop for Unstage {
+    fn drop(&mut self) {
+        STAGED.set(None);
+    }
+}
+
+impl Layer for Reloadable {
+    fn lookup(&self, key: &str) -> anyhow::Result<Option<(String, Source)>> {
+        let address = self as *const Self as usize;
+        let staged = STAGED.with_borrow(|staged| match staged {
+            Some((x, dotenv)) if *x == address => Some(dotenv.clone()),
+            _ => None,
+        });
+        staged.unwrap_or_else(|| self.snapshot()).lookup(key)
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{EnvFlagWithDefault, EnvFlagWithDefaultStr};
+use std::hash::{Hash, Hasher};
+
+/// A percentage of ids for which a feature is enabled, e.g., `25%`.
+///
+/// Ids are bucketed by a stable hash, salted by the key of the flag,
+/// so the same id always gets the same answer,
+/// and increasing the percentage only adds ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rollout {
+    /// The percentage in hundredths, i.e., from 0 to 10000.
+    basis_points: u16,
+}
+
+impl Rollout {
+    pub const OFF: Rollout = Rollout { basis_points: 0 };
+    pub const ON: Rollout = Rollout {
+        basis_points: 10000,
+    };
+
+    /// Returns `None` unless `percent` is between 0 and 100.
+    pub fn from_percent(percent: f64) -> Option<Self> {
+        if !(0.0..=100.0).contains(&percent) {
+            return None;
+        }
+        let basis_points = (percent * 100.0).round() as u16;
+        Some(Self { basis_points })
+    }
+
+    pub fn percent(&self) -> f64 {
+        f64::from(self.basis_points) / 100.0
+    }
+
+    /// Whether the feature of flag `key` is enabled for `id`.
+    pub fn is_enabled_for<H: Hash + ?Sized>(&self, key: &str, id: &H) -> bool {
+        bucket(key, id, 10000) < u64::from(self.basis_points)
+    }
+}
+
+impl std::fmt::Display for Rollout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}%", self.percent())
+    }
+}
+
+/// Parses percentages like `25%`, `12.5%` or `25`.
+/// `true` and `false` are accepted as `100%` and `0%` respectively,
+/// so bool flags can be turned into rollout flags.
+pub fn rollout_parser(key: &str, val: &str) -> anyhow::Result<Rollout> {
+    let res = match val.trim() {
+        "true" => Some(Rollout::ON),
+        "false" => Some(Rollout::OFF),
+        x => {
+            let x = x.strip_suffix('%').unwrap_or(x).trim_end();
+            x.parse().ok().and_then(Rollout::from_percent)
+        }
+    };
+    res.ok_or_else(|| {
+        anyhow::Error::msg(format!(
+            "Unrecognized ENV \"{}\": \"{}\" is not a percentage between 0% and 100%",
+            key, val
+        ))
+    })
+}
+
+impl<DefaultT> EnvFlagWithDefault<Rollout, DefaultT>
+where
+    DefaultT: ToOwned<Owned = Rollout> + 'static + ?Sized,
+{
+    /// Whether the feature is enabled for `id`, e.g., a user or a tenant.
+    pub fn is_enabled_for<H: Hash + ?Sized>(&'static self, id: &H) -> anyhow::Result<bool> {
+        Ok(self.fetch()?.is_enabled_for(self.key(), id))
+    }
+}
+
+impl EnvFlagWithDefaultStr<Rollout> {
+    /// Whether the feature is enabled for `id`, e.g., a user or a tenant.
+    pub fn is_enabled_for<H: Hash + ?Sized>(&'static self, id: &H) -> anyhow::Result<bool> {
+        Ok(self.fetch()?.is_enabled_for(self.key(), id))
+    }
+}
+
+/// Assigns `id` to one of `buckets` by a stable hash salted by `key`.
+pub(crate) fn bucket<H: Hash + ?Sized>(key: &str, id: &H, buckets: u64) -> u64 {
+    let mut hasher = Fnv1a::default();
+    key.hash(&mut hasher);
+    id.hash(&mut hasher);
+    hasher.finish() % buckets
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is stable across Rust releases.
+/// Integers are hashed in little endian so that it is stable across platforms too.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter() {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
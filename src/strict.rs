@@ -0,0 +1,143 @@
+//! Detection of env variables which look like flags but are not, e.g., typos.
+//!
+//! ```
+//! use rs_envflag::strict::{check_unknown, Policy};
+//! use rs_envflag::Flag;
+//! use rs_envflag_macros::*;
+//!
+//! #[envflag(default=30)]
+//! const MYSVC_TIMEOUT: i64;
+//!
+//! const FLAGS: &[&dyn Flag] = &[&MYSVC_TIMEOUT];
+//!
+//! // With `MYSVC_TIMOUT=60`, it warns
+//! // "Unknown ENV MYSVC_TIMOUT. Did you mean MYSVC_TIMEOUT?"
+//! check_unknown(FLAGS, &["MYSVC_"], Policy::Warn).unwrap();
+//! ```
+
+use crate::{profile_key, Flag};
+use std::fmt;
+
+/// What to do with unknown env variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Prints them to stderr.
+    Warn,
+    /// Fails.
+    Error,
+}
+
+/// An env variable which matches a prefix but no flag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unknown {
+    pub key: String,
+    /// The most similar name of flags, if any is similar enough.
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Unknown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown ENV {}.", self.key)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " Did you mean {}?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Scans the process environment for variables starting with any of `prefixes`
+/// but corresponding to none of `flags`.
+pub fn unknown_vars(flags: &[&dyn Flag], prefixes: &[&str]) -> Vec<Unknown> {
+    let profile_key = profile_key();
+    let mut res: Vec<Unknown> = std::env::vars_os()
+        .map(|(key, _)| key.to_string_lossy().into_owned())
+        .filter(|key| prefixes.iter().any(|prefix| key.starts_with(prefix)))
+        .filter(|key| key != profile_key)
+        .filter(|key| flags.iter().all(|flag| !flag.keys().contains(key)))
+        .map(|key| {
+            let suggestion = suggest(flags, &key);
+            Unknown { key, suggestion }
+        })
+        .collect();
+    res.sort_by(|a, b| a.key.cmp(&b.key));
+    res
+}
+
+/// Checks unknown env variables as per `policy`.
+/// Under `Policy::Warn`, it succeeds and returns all unknown env variables.
+pub fn check_unknown(
+    flags: &[&dyn Flag],
+    prefixes: &[&str],
+    policy: Policy,
+) -> anyhow::Result<Vec<Unknown>> {
+    let res = unknown_vars(flags, prefixes);
+    match policy {
+        Policy::Warn => {
+            for x in res.iter() {
+                eprintln!("{}", x);
+            }
+            Ok(res)
+        }
+        Policy::Error if res.is_empty() => Ok(res),
+        Policy::Error => {
+            let msg: Vec<String> = res.iter().map(|x| x.to_string()).collect();
+            anyhow::bail!("{}", msg.join(" "));
+        }
+    }
+}
+
+/// The most similar name of `flags`, where templated parts are filled by
+/// the corresponding parts of `key`.
+fn suggest(flags: &[&dyn Flag], key: &str) -> Option<String> {
+    let threshold = 2.max(key.chars().count() / 4);
+    flags
+        .iter()
+        .flat_map(|flag| flag.key_templates())
+        .map(|template| match_template(&template, key))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+/// The distance between `key` and the most similar name of `template`, and the name.
+fn match_template(template: &str, key: &str) -> (usize, String) {
+    let Some((prefix, suffix)) = template.split_once("{}") else {
+        return (edit_distance(template, key), template.to_string());
+    };
+    let key: Vec<char> = key.chars().collect();
+    // distances from `prefix` to prefixes of `key`
+    let heads = edit_distances(prefix.chars(), key.iter().copied());
+    // distances from `suffix` to suffixes of `key`, from the shortest
+    let tails = edit_distances(suffix.chars().rev(), key.iter().rev().copied());
+    let mut res = (usize::MAX, String::new());
+    for start in 0..key.len() {
+        for end in start + 1..=key.len() {
+            let distance = heads[start] + tails[key.len() - end];
+            if distance < res.0 {
+                let index: String = key[start..end].iter().collect();
+                res = (distance, format!("{}{}{}", prefix, index, suffix));
+            }
+        }
+    }
+    res
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    *edit_distances(a.chars(), b.chars()).last().unwrap()
+}
+
+/// Levenshtein distances between `a` and every prefix of `b`, from the empty one.
+fn edit_distances(a: impl Iterator<Item = char>, b: impl Iterator<Item = char>) -> Vec<usize> {
+    let b: Vec<char> = b.collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev
+}
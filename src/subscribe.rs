@@ -0,0 +1,155 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::ThreadId;
+
+type Check = Arc<Mutex<dyn FnMut() + Send>>;
+type Call = Box<dyn FnOnce() + Send>;
+
+static SUBSCRIPTIONS: Mutex<BTreeMap<u64, Check>> = Mutex::new(BTreeMap::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Callbacks of changes, which are called in order by one thread at a time.
+struct Queue {
+    calls: VecDeque<(u64, Call)>,
+    /// The thread calling callbacks, if any.
+    draining: Option<ThreadId>,
+    /// Numbers of calls ever queued and ever finished.
+    queued: u64,
+    finished: u64,
+}
+
+static QUEUE: Mutex<Queue> = Mutex::new(Queue {
+    calls: VecDeque::new(),
+    draining: None,
+    queued: 0,
+    finished: 0,
+});
+static DRAINED: Condvar = Condvar::new();
+
+/// A subscription to changes of a flag, which is cancelled once dropped.
+#[must_use = "the subscription is cancelled once dropped"]
+#[derive(Debug)]
+pub struct Subscription {
+    id: u64,
+}
+
+impl Subscription {
+    /// Keeps the subscription for the rest of the process.
+    pub fn detach(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        SUBSCRIPTIONS.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Re-fetches all subscribed flags and notifies subscribers of changed values.
+///
+/// It is called when the installed resolver or a reloadable store changes.
+/// Applications which change the process environment themselves have to call it.
+///
+/// Callbacks are called one at a time, in order of changes, by whichever thread notifies first,
+/// and the others wait for their changes to be called back.
+/// Callbacks may change flags, e.g., by `set_override()`,
+/// and callbacks of these changes are called after them.
+pub fn notify() {
+    let checks: Vec<Check> = SUBSCRIPTIONS.lock().unwrap().values().cloned().collect();
+    for check in checks.iter() {
+        (check.lock().unwrap())();
+    }
+    let mut queue = QUEUE.lock().unwrap();
+    let me = std::thread::current().id();
+    // Called by a callback, whose caller calls the rest.
+    if queue.draining == Some(me) {
+        return;
+    }
+    let target = queue.queued;
+    loop {
+        if queue.finished >= target {
+            return;
+        }
+        match queue.draining {
+            Some(_) => queue = DRAINED.wait(queue).unwrap(),
+            None => break,
+        }
+    }
+    queue.draining = Some(me);
+    Drain(Some(queue)).run();
+}
+
+/// Calls queued callbacks, and hands it over to other threads once dropped, even on panics.
+/// The queue is unlocked, i.e., `None`, while a callback is called.
+struct Drain(Option<MutexGuard<'static, Queue>>);
+
+impl Drain {
+    fn run(&mut self) {
+        loop {
+            let Some((id, call)) = self.0.as_mut().unwrap().calls.pop_front() else {
+                return;
+            };
+            self.0 = None;
+            // Subscriptions may be cancelled after their changes are queued.
+            if SUBSCRIPTIONS.lock().unwrap().contains_key(&id) {
+                call();
+            }
+            let mut queue = QUEUE.lock().unwrap();
+            queue.finished += 1;
+            self.0 = Some(queue);
+        }
+    }
+}
+
+impl Drop for Drain {
+    fn drop(&mut self) {
+        let mut queue = self.0.take().unwrap_or_else(|| {
+            // A callback panicked.
+            let mut queue = QUEUE.lock().unwrap();
+            queue.finished += 1;
+            queue
+        });
+        queue.draining = None;
+        DRAINED.notify_all();
+    }
+}
+
+/// Subscribes to changes of what `fetch` returns.
+/// Errors are ignored, so `callback` never sees values which fail to parse.
+pub(crate) fn subscribe<T, F, C>(fetch: F, callback: C) -> Subscription
+where
+    T: PartialEq + Send + 'static,
+    F: Fn() -> anyhow::Result<T> + Send + 'static,
+    C: FnMut(&T, &T) + Send + 'static,
+{
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    // The last value and the callback, which are only used by calls in the queue.
+    let state = Arc::new(Mutex::new((fetch().ok(), callback)));
+    let check = move || {
+        let Ok(new) = fetch() else {
+            return;
+        };
+        let state = state.clone();
+        let call = move || {
+            let (last, callback) = &mut *state.lock().unwrap();
+            match last {
+                Some(old) if *old == new => {}
+                Some(old) => {
+                    callback(old, &new);
+                    *last = Some(new);
+                }
+                None => *last = Some(new),
+            }
+        };
+        let mut queue = QUEUE.lock().unwrap();
+        queue.calls.push_back((id, Box::new(call)));
+        queue.queued += 1;
+    };
+    SUBSCRIPTIONS
+        .lock()
+        .unwrap()
+        .insert(id, Arc::new(Mutex::new(check)));
+    Subscription { id }
+}
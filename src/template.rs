@@ -0,0 +1,260 @@
+use crate::{
+    Diagnosis, EnvFlag, EnvFlagRequired, EnvFlagWithDefault, EnvFlagWithDefaultFn,
+    EnvFlagWithDefaultStr, Flag, Installed, Kind, Layer, Meta, Source,
+};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+
+/// A flag whose name is a template like `SHARD_{}_HOST`,
+/// which is fetched for specific indices, e.g., `SHARD_3_HOST` by `fetch_for(3)`.
+///
+/// It shares the parser and the default of `flag` for all indices.
+pub struct Templated<F> {
+    pub flag: F,
+}
+
+/// Flags which can be fetched with their names renamed, i.e., templated.
+pub trait Renamed: Flag {
+    /// What `fetch_for()` returns, e.g., `Option<T>` for flags without defaults.
+    type Value;
+    /// What `fetch_for_with_source()` returns.
+    type WithSource;
+
+    /// Fetches with names of the flag mapped by `rename`.
+    fn fetch_renamed_from<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<Self::WithSource>;
+
+    fn split_source(x: Self::WithSource) -> (Self::Value, Option<Source>);
+}
+
+impl<T: Debug> Renamed for EnvFlag<T> {
+    type Value = Option<T>;
+    type WithSource = Option<(T, Source)>;
+
+    fn fetch_renamed_from<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<Self::WithSource> {
+        self.fetch_renamed(layer, rename)
+    }
+
+    fn split_source(x: Self::WithSource) -> (Self::Value, Option<Source>) {
+        match x {
+            Some((x, source)) => (Some(x), Some(source)),
+            None => (None, None),
+        }
+    }
+}
+
+impl<T, DefaultT> Renamed for EnvFlagWithDefault<T, DefaultT>
+where
+    T: Clone + Debug,
+    DefaultT: ToOwned<Owned = T> + Sync + 'static + ?Sized,
+{
+    type Value = T;
+    type WithSource = (T, Source);
+
+    fn fetch_renamed_from<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<Self::WithSource> {
+        self.fetch_renamed(layer, rename)
+    }
+
+    fn split_source((x, source): Self::WithSource) -> (Self::Value, Option<Source>) {
+        (x, Some(source))
+    }
+}
+
+impl<T: Debug> Renamed for EnvFlagWithDefaultStr<T> {
+    type Value = T;
+    type WithSource = (T, Source);
+
+    fn fetch_renamed_from<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<Self::WithSource> {
+        self.fetch_renamed(layer, rename)
+    }
+
+    fn split_source((x, source): Self::WithSource) -> (Self::Value, Option<Source>) {
+        (x, Some(source))
+    }
+}
+
+impl<T: Debug> Renamed for EnvFlagWithDefaultFn<T> {
+    type Value = T;
+    type WithSource = (T, Source);
+
+    fn fetch_renamed_from<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<Self::WithSource> {
+        self.fetch_renamed(layer, rename)
+    }
+
+    fn split_source((x, source): Self::WithSource) -> (Self::Value, Option<Source>) {
+        (x, Some(source))
+    }
+}
+
+impl<T: Debug> Renamed for EnvFlagRequired<T> {
+    type Value = T;
+    type WithSource = (T, Source);
+
+    fn fetch_renamed_from<L: Layer + ?Sized>(
+        &self,
+        layer: &L,
+        rename: &dyn Fn(&str) -> String,
+    ) -> anyhow::Result<Self::WithSource> {
+        self.fetch_renamed(layer, rename)
+    }
+
+    fn split_source((x, source): Self::WithSource) -> (Self::Value, Option<Source>) {
+        (x, Some(source))
+    }
+}
+
+impl<F: Renamed> Templated<F> {
+    pub fn template(&self) -> &'static str {
+        self.flag.key()
+    }
+
+    /// The name of the env variable for `index`.
+    pub fn key_for(&self, index: impl Display) -> String {
+        self.template().replace("{}", &index.to_string())
+    }
+
+    /// Indices which are set in the process environment, under any name of the flag.
+    /// They are sorted by length and then lexicographically,
+    /// so numeric indices are in numeric order.
+    pub fn indices(&self) -> Vec<String> {
+        let templates = self.flag.keys();
+        let mut res: Vec<String> = std::env::vars_os()
+            .filter_map(|(key, _)| {
+                let key = key.to_str()?.to_string();
+                // The most specific name wins, e.g., `SH_{}_FILE` over `SH_{}` for `SH_3_FILE`.
+                templates
+                    .iter()
+                    .filter_map(|x| match_template(x, &key))
+                    .min_by_key(|x| x.len())
+            })
+            .collect();
+        res.sort_by(|a, b| (a.len(), a).cmp(&(b.len(), b)));
+        res.dedup();
+        res
+    }
+
+    pub fn fetch_for(&'static self, index: impl Display) -> anyhow::Result<F::Value> {
+        self.fetch_for_from(index, &Installed)
+    }
+
+    pub fn fetch_for_from<L: Layer + ?Sized>(
+        &self,
+        index: impl Display,
+        layer: &L,
+    ) -> anyhow::Result<F::Value> {
+        let res = self.fetch_for_from_with_source(index, layer)?;
+        Ok(F::split_source(res).0)
+    }
+
+    pub fn fetch_for_with_source(
+        &'static self,
+        index: impl Display,
+    ) -> anyhow::Result<F::WithSource> {
+        self.fetch_for_from_with_source(index, &Installed)
+    }
+
+    pub fn fetch_for_from_with_source<L: Layer + ?Sized>(
+        &self,
+        index: impl Display,
+        layer: &L,
+    ) -> anyhow::Result<F::WithSource> {
+        let index = index.to_string();
+        self.flag
+            .fetch_renamed_from(layer, &|key| key.replace("{}", &index))
+            .with_context(|| format!("Fail to fetch ENV {}", self.key_for(&index)))
+    }
+}
+
+/// The part of `key` matching `{}` in `template`, if any.
+fn match_template(template: &str, key: &str) -> Option<String> {
+    let (prefix, suffix) = template.split_once("{}")?;
+    let index = key.strip_prefix(prefix)?.strip_suffix(suffix)?;
+    if index.is_empty() {
+        return None;
+    }
+    Some(index.to_string())
+}
+
+impl<F> Flag for Templated<F>
+where
+    F: Renamed,
+    F::Value: Debug,
+{
+    fn key(&self) -> &'static str {
+        self.flag.key()
+    }
+
+    /// Names for indices which are set in the process environment.
+    fn keys(&self) -> Vec<String> {
+        let templates = self.flag.keys();
+        self.indices()
+            .iter()
+            .flat_map(|index| templates.iter().map(|x| x.replace("{}", index)))
+            .collect()
+    }
+
+    fn key_templates(&self) -> Vec<String> {
+        self.flag.keys()
+    }
+
+    fn meta(&self) -> &Meta {
+        self.flag.meta()
+    }
+
+    fn has_default(&self) -> bool {
+        self.flag.has_default()
+    }
+
+    fn required(&self) -> bool {
+        self.flag.required()
+    }
+
+    /// Values of all indices, from names to values, with the source of the first one.
+    fn diagnose(&self) -> Diagnosis {
+        let mut values = BTreeMap::new();
+        let mut first = None;
+        for index in self.indices() {
+            match self.fetch_for_from_with_source(&index, &Installed) {
+                Ok(x) => {
+                    let (value, source) = F::split_source(x);
+                    first = first.or(source);
+                    values.insert(self.key_for(&index), value);
+                }
+                Err(e) => return Diagnosis::new::<()>(self.flag.meta(), self.flag.key(), Err(e)),
+            }
+        }
+        let res = Ok(first.map(|source| (values, source)));
+        Diagnosis::new(self.flag.meta(), self.flag.key(), res)
+    }
+
+    fn validate_from(&self, layer: &dyn Layer) -> anyhow::Result<()> {
+        for index in self.indices() {
+            self.fetch_for_from(&index, layer)?;
+        }
+        Ok(())
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Templated
+    }
+}
@@ -0,0 +1,103 @@
+use crate::rollout::bucket;
+use crate::EnvFlagWithDefaultStr;
+use std::hash::Hash;
+
+/// Weighted variants of an experiment, e.g., `control:50,treatment_a:25,treatment_b:25`.
+///
+/// Like `Rollout`, ids are assigned by a stable hash salted by the key of the flag.
+/// Since they cannot be constructed in const contexts,
+/// defaults are written as strings by `default_str`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Variants {
+    /// Names and weights, where weights sum to 100.
+    variants: Vec<(String, u8)>,
+}
+
+impl Variants {
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.variants.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The weight of variant `name` in percent.
+    pub fn weight(&self, name: &str) -> Option<u8> {
+        self.variants
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, weight)| *weight)
+    }
+
+    /// The variant of flag `key` for `id`.
+    pub fn variant_for<H: Hash + ?Sized>(&self, key: &str, id: &H) -> &str {
+        let mut bucket = bucket(key, id, 100);
+        for (name, weight) in self.variants.iter() {
+            match bucket.checked_sub(u64::from(*weight)) {
+                Some(x) => bucket = x,
+                None => return name,
+            }
+        }
+        unreachable!("Weights of variants sum to 100.")
+    }
+}
+
+impl std::fmt::Display for Variants {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, weight)) in self.variants.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}:{}", name, weight)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses variants like `control:50,treatment_a:25,treatment_b:25`,
+/// whose names must be unique and whose weights must sum to 100.
+pub fn variants_parser(key: &str, val: &str) -> anyhow::Result<Variants> {
+    let mut variants: Vec<(String, u8)> = vec![];
+    for item in val.split(',') {
+        let (name, weight) = item
+            .split_once(':')
+            .map(|(name, weight)| (name.trim(), weight.trim()))
+            .ok_or_else(|| {
+                anyhow::Error::msg(format!(
+                    "Unrecognized ENV \"{}\": expect \"name:weight\" but \"{}\"",
+                    key,
+                    item.trim()
+                ))
+            })?;
+        if name.is_empty() {
+            anyhow::bail!("Unrecognized ENV \"{}\": empty name of variant", key);
+        }
+        if variants.iter().any(|(x, _)| x == name) {
+            anyhow::bail!(
+                "Unrecognized ENV \"{}\": duplicate variant \"{}\"",
+                key,
+                name
+            );
+        }
+        let weight = weight.parse().map_err(|e| {
+            anyhow::Error::msg(format!(
+                "Unrecognized ENV \"{}\": weight of variant \"{}\": \"{:?}\"",
+                key, name, e
+            ))
+        })?;
+        variants.push((name.to_string(), weight));
+    }
+    let sum: u32 = variants.iter().map(|(_, weight)| u32::from(*weight)).sum();
+    if sum != 100 {
+        anyhow::bail!(
+            "Unrecognized ENV \"{}\": weights of variants sum to {} but not 100",
+            key,
+            sum
+        );
+    }
+    Ok(Variants { variants })
+}
+
+impl EnvFlagWithDefaultStr<Variants> {
+    /// The variant for `id`, e.g., a user or a tenant.
+    pub fn variant_for<H: Hash + ?Sized>(&'static self, id: &H) -> anyhow::Result<String> {
+        Ok(self.fetch()?.variant_for(self.key(), id).to_string())
+    }
+}
@@ -0,0 +1,67 @@
+//! Helpers shared by integration tests.
+#![allow(dead_code)]
+
+use rs_envflag::{DotEnv, Resolver};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+/// A layer of `.env` content, as if it is read from `test.env`.
+pub fn dotenv(content: &str) -> DotEnv {
+    DotEnv::parse("test.env", content).unwrap()
+}
+
+/// Writes `content` to a file in the temp dir, whose name is unique to the process.
+pub fn temp_file(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rs_envflag_{}_{}", std::process::id(), name));
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+static GLOBAL: Mutex<()> = Mutex::new(());
+
+/// Holds process-global state, i.e., the installed resolver, overrides and env variables,
+/// so tests which change it run one at a time.
+/// On drop, even on panics, the resolver is uninstalled and the state is restored.
+pub struct Global {
+    vars: Vec<(String, Option<OsString>)>,
+    cleanups: Vec<Box<dyn FnOnce()>>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Global {
+    pub fn lock() -> Self {
+        Self {
+            vars: vec![],
+            cleanups: vec![],
+            // A failed test must not fail the others.
+            _lock: GLOBAL.lock().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+
+    /// Sets an env variable, which is restored on drop.
+    pub fn set_var(&mut self, key: &str, value: impl AsRef<std::ffi::OsStr>) {
+        self.vars.push((key.to_string(), std::env::var_os(key)));
+        std::env::set_var(key, value);
+    }
+
+    /// Calls `f` on drop, e.g., to clear overrides.
+    pub fn defer(&mut self, f: impl FnOnce() + 'static) {
+        self.cleanups.push(Box::new(f));
+    }
+}
+
+impl Drop for Global {
+    fn drop(&mut self) {
+        Resolver::uninstall();
+        for f in self.cleanups.drain(..).rev() {
+            f();
+        }
+        for (key, value) in self.vars.drain(..).rev() {
+            match value {
+                Some(value) => std::env::set_var(key, value),
+                None => std::env::remove_var(key),
+            }
+        }
+    }
+}
@@ -0,0 +1,6 @@
+use rs_envflag_macros::*;
+
+#[envflag(default_str = "info", default(dev) = "debug")]
+const LOG_LEVEL: String;
+
+fn main() {}
@@ -0,0 +1,6 @@
+use rs_envflag_macros::*;
+
+#[envflag(default(dev) = "debug")]
+const LOG_LEVEL: String;
+
+fn main() {}
@@ -0,0 +1,6 @@
+use rs_envflag_macros::*;
+
+#[envflag(default = &CONTROL)]
+const CHECKOUT: rs_envflag::Variants;
+
+fn main() {}
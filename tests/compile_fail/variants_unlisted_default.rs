@@ -0,0 +1,6 @@
+use rs_envflag_macros::*;
+
+#[envflag(variants = ["control", "treatment"], default_str = "control:50,other:50")]
+const CHECKOUT: rs_envflag::Variants;
+
+fn main() {}
@@ -0,0 +1,6 @@
+use rs_envflag_macros::*;
+
+#[envflag]
+const CHECKOUT: Option<rs_envflag::Variants>;
+
+fn main() {}
@@ -0,0 +1,98 @@
+use rs_envflag::diagnostics::{dump, Format};
+use rs_envflag::{Flag, Source};
+use rs_envflag_macros::*;
+
+#[envflag(default = 8080)]
+const DIAG_PORT: i64;
+
+#[envflag(secret)]
+const DIAG_PASSWORD: Option<String>;
+
+#[envflag]
+const DIAG_ABSENT: Option<String>;
+
+#[envflag]
+const DIAG_INVALID: Option<i64>;
+
+const FLAGS: &[&dyn Flag] = &[&DIAG_PORT, &DIAG_PASSWORD, &DIAG_ABSENT, &DIAG_INVALID];
+
+fn setup() {
+    std::env::set_var("DIAG_PASSWORD", "p@ss word");
+    std::env::set_var("DIAG_INVALID", "x");
+}
+
+#[test]
+fn diagnose() {
+    setup();
+    let port = DIAG_PORT.diagnose();
+    assert_eq!(port.value.as_deref(), Some("8080"));
+    assert_eq!(port.source, Some(Source::Default { profile: None }));
+    assert!(port.is_ok());
+    assert!(port.is_defaulted());
+
+    let password = DIAG_PASSWORD.diagnose();
+    assert_eq!(password.value.as_deref(), Some("<redacted>"));
+    assert!(!password.is_defaulted());
+
+    let absent = DIAG_ABSENT.diagnose();
+    assert_eq!(absent.value, None);
+    assert!(absent.is_ok());
+
+    let invalid = DIAG_INVALID.diagnose();
+    assert_eq!(invalid.value, None);
+    assert!(!invalid.is_ok());
+}
+
+#[test]
+fn text() {
+    setup();
+    let res = dump(FLAGS, Format::Text);
+    let lines: Vec<&str> = res.lines().collect();
+    assert_eq!(lines[0], "active profile: -");
+    assert_eq!(
+        lines[1],
+        "KEY            VALUE       SOURCE             STATUS"
+    );
+    assert_eq!(lines[2], "DIAG_PORT      8080        default            ok");
+    assert_eq!(lines[3], "DIAG_PASSWORD  <redacted>  env DIAG_PASSWORD  ok");
+    assert_eq!(lines[4], "DIAG_ABSENT    -           -                  ok");
+    assert!(lines[5].starts_with("DIAG_INVALID   -           -                  error: "));
+    assert!(!res.contains("p@ss"));
+}
+
+#[test]
+fn json() {
+    setup();
+    let res = dump(FLAGS, Format::Json);
+    assert!(res.starts_with(
+        r#"{"profile":null,"flags":[{"key":"DIAG_PORT","value":"8080","source":"default","defaulted":true,"status":"ok","error":null},"#
+    ));
+    assert!(res.contains(
+        r#"{"key":"DIAG_ABSENT","value":null,"source":null,"defaulted":false,"status":"ok","error":null}"#
+    ));
+    assert!(res.contains(r#"{"key":"DIAG_INVALID","value":null,"source":null,"defaulted":false,"status":"error","error":"#));
+    assert!(!res.contains("p@ss"));
+}
+
+#[test]
+fn key_value() {
+    setup();
+    let res = dump(FLAGS, Format::KeyValue);
+    let lines: Vec<&str> = res.lines().collect();
+    assert_eq!(lines[0], r#"profile="""#);
+    assert_eq!(
+        lines[1],
+        "key=DIAG_PORT value=8080 source=default defaulted=true status=ok"
+    );
+    assert_eq!(
+        lines[2],
+        r#"key=DIAG_PASSWORD value=<redacted> source="env DIAG_PASSWORD" defaulted=false status=ok"#
+    );
+    assert_eq!(
+        lines[3],
+        r#"key=DIAG_ABSENT value="" source="" defaulted=false status=ok"#
+    );
+    assert!(lines[4].starts_with(
+        r#"key=DIAG_INVALID value="" source="" defaulted=false status=error error=""#
+    ));
+}
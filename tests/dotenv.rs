@@ -0,0 +1,74 @@
+use rs_envflag::{DotEnv, Env};
+use rs_envflag_macros::*;
+
+#[test]
+fn syntax() {
+    let dotenv = DotEnv::parse(
+        ".env",
+        r#"
+# a comment
+PLAIN=abc
+  SPACED = x y  
+export EXPORTED=1
+INLINE=abc # a comment
+HASH=abc#def
+EMPTY=
+EMPTY_W_COMMENT= # a comment
+SINGLE='a\nb $X # c'
+DOUBLE="a\tb\n\"c\" \$X # d" # a comment
+MULTI="line 1
+line 2"
+AFTER_MULTI=yes
+"#,
+    )
+    .unwrap();
+    assert_eq!(dotenv.get("PLAIN"), Some("abc"));
+    assert_eq!(dotenv.get("SPACED"), Some("x y"));
+    assert_eq!(dotenv.get("EXPORTED"), Some("1"));
+    assert_eq!(dotenv.get("INLINE"), Some("abc"));
+    assert_eq!(dotenv.get("HASH"), Some("abc#def"));
+    assert_eq!(dotenv.get("EMPTY"), Some(""));
+    assert_eq!(dotenv.get("EMPTY_W_COMMENT"), Some(""));
+    assert_eq!(dotenv.get("SINGLE"), Some("a\\nb $X # c"));
+    assert_eq!(dotenv.get("DOUBLE"), Some("a\tb\n\"c\" $X # d"));
+    assert_eq!(dotenv.get("MULTI"), Some("line 1\nline 2"));
+    assert_eq!(dotenv.get("AFTER_MULTI"), Some("yes"));
+    assert_eq!(dotenv.get("ABSENT"), None);
+}
+
+#[test]
+fn syntax_errors() {
+    let err = DotEnv::parse("x.env", "A=1\nB 2\n").unwrap_err();
+    assert_eq!(err.to_string(), "x.env:2: Expect '=' after \"B\"");
+
+    let err = DotEnv::parse("x.env", "A=1\n\nB=\"abc\nC=3\n").unwrap_err();
+    assert_eq!(err.to_string(), "x.env:3: Unterminated double quote");
+
+    let err = DotEnv::parse("x.env", "A='abc' def\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "x.env:1: Unexpected characters after the quoted value of \"A\""
+    );
+
+    let err = DotEnv::parse("x.env", "1A=1\n").unwrap_err();
+    assert_eq!(err.to_string(), "x.env:1: Invalid key");
+}
+
+#[test]
+fn under_env() {
+    #[envflag]
+    const DOTENV_ONLY: Option<i64>;
+
+    #[envflag(default = 0)]
+    const DOTENV_OVERRIDDEN: i64;
+
+    #[envflag(default = "xixi")]
+    const DOTENV_ABSENT: String;
+
+    let dotenv = DotEnv::parse(".env", "DOTENV_ONLY=1\nDOTENV_OVERRIDDEN=2\n").unwrap();
+    std::env::set_var("DOTENV_OVERRIDDEN", "3");
+    assert!(DOTENV_ONLY.fetch().unwrap().is_none());
+    assert_eq!(DOTENV_ONLY.fetch_from(&(Env, &dotenv)).unwrap(), Some(1));
+    assert_eq!(DOTENV_OVERRIDDEN.fetch_from(&(Env, &dotenv)).unwrap(), 3);
+    assert_eq!(DOTENV_ABSENT.fetch_from(&(Env, &dotenv)).unwrap(), "xixi");
+}
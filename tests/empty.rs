@@ -0,0 +1,66 @@
+use rs_envflag::DotEnv;
+use rs_envflag_macros::*;
+
+#[envflag(empty = "unset", default = 8080)]
+const EMPTY_PORT: i64;
+
+#[envflag(empty = "unset")]
+const EMPTY_NAME: Option<String>;
+
+#[envflag(empty = "error", default = "info")]
+const EMPTY_LEVEL: String;
+
+#[envflag(empty = "value")]
+const EMPTY_SUFFIX: Option<String>;
+
+#[envflag(trim = true, default = 1)]
+const EMPTY_REPLICAS: i64;
+
+#[envflag(trim, empty = "unset", default = "main")]
+const EMPTY_BRANCH: String;
+
+#[envflag(default = 1)]
+const EMPTY_WORKERS: i64;
+
+#[test]
+fn empty() {
+    let layer = DotEnv::parse(
+        "test.env",
+        r#"
+EMPTY_PORT=
+EMPTY_NAME=
+EMPTY_LEVEL=
+EMPTY_SUFFIX=
+EMPTY_WORKERS=
+"#,
+    )
+    .unwrap();
+    assert_eq!(EMPTY_PORT.fetch_from(&layer).unwrap(), 8080);
+    assert_eq!(EMPTY_NAME.fetch_from(&layer).unwrap(), None);
+    assert_eq!(
+        EMPTY_LEVEL.fetch_from(&layer).unwrap_err().to_string(),
+        "Empty ENV \"EMPTY_LEVEL\""
+    );
+    assert_eq!(
+        EMPTY_SUFFIX.fetch_from(&layer).unwrap().as_deref(),
+        Some("")
+    );
+    // By default, empty values go to parsers.
+    assert!(EMPTY_WORKERS.fetch_from(&layer).is_err());
+}
+
+#[test]
+fn trim() {
+    let layer = DotEnv::parse(
+        "test.env",
+        "EMPTY_REPLICAS=\" 3 \"\nEMPTY_BRANCH=\"  \"\nEMPTY_WORKERS=\" 3\"",
+    )
+    .unwrap();
+    assert_eq!(EMPTY_REPLICAS.fetch_from(&layer).unwrap(), 3);
+    assert_eq!(EMPTY_BRANCH.fetch_from(&layer).unwrap(), "main");
+    assert!(EMPTY_WORKERS.fetch_from(&layer).is_err());
+
+    EMPTY_REPLICAS.set_override(" 5\n").unwrap();
+    assert_eq!(EMPTY_REPLICAS.fetch().unwrap(), 5);
+    EMPTY_REPLICAS.clear_override();
+}
@@ -0,0 +1,105 @@
+use rs_envflag::strict::unknown_vars;
+use rs_envflag::{Flag, Source};
+use rs_envflag_macros::*;
+
+mod common;
+use common::dotenv;
+
+#[envflag(env_name = ["FB_MYSVC_PORT", "FB_PORT"], default = 8080)]
+const PORT: i64;
+
+#[envflag(env_name = ["FB_MYSVC_TOKEN", "FB_TOKEN"], allow_file)]
+const TOKEN: Option<String>;
+
+const FLAGS: &[&dyn Flag] = &[&PORT, &TOKEN];
+
+fn source(key: &str) -> Source {
+    Source::File {
+        path: "test.env".into(),
+        key: key.to_string(),
+    }
+}
+
+#[test]
+fn fallbacks_key() {
+    assert_eq!(PORT.key(), "FB_MYSVC_PORT");
+}
+
+#[test]
+fn fallbacks_primary_first() {
+    assert_eq!(
+        PORT.fetch_from_with_source(&dotenv("FB_PORT=1\nFB_MYSVC_PORT=2"))
+            .unwrap(),
+        (2, source("FB_MYSVC_PORT"))
+    );
+}
+
+#[test]
+fn fallbacks_fallback() {
+    assert_eq!(
+        PORT.fetch_from_with_source(&dotenv("FB_PORT=1")).unwrap(),
+        (1, source("FB_PORT"))
+    );
+}
+
+#[test]
+fn fallbacks_default() {
+    assert_eq!(
+        PORT.fetch_from_with_source(&dotenv("")).unwrap(),
+        (8080, Source::Default { profile: None })
+    );
+}
+
+#[test]
+fn fallbacks_invalid_not_skipped() {
+    assert!(PORT
+        .fetch_from(&dotenv("FB_MYSVC_PORT=x\nFB_PORT=1"))
+        .is_err());
+}
+
+#[test]
+fn fallbacks_error_names_fallback() {
+    let e = PORT.fetch_from(&dotenv("FB_PORT=x")).unwrap_err();
+    let msg = format!("{:#}", e);
+    assert!(msg.contains("\"FB_PORT\""), "{}", msg);
+    assert!(!msg.contains("FB_MYSVC_PORT"), "{}", msg);
+}
+
+#[test]
+fn fallbacks_env() {
+    std::env::set_var("FB_PORT", "3");
+    assert_eq!(
+        PORT.fetch_with_source().unwrap(),
+        (
+            3,
+            Source::Env {
+                key: "FB_PORT".to_string()
+            }
+        )
+    );
+    assert_eq!(PORT.diagnose().source.unwrap().to_string(), "env FB_PORT");
+}
+
+#[test]
+fn fallbacks_keys() {
+    assert_eq!(
+        TOKEN.keys(),
+        [
+            "FB_MYSVC_TOKEN",
+            "FB_MYSVC_TOKEN_FILE",
+            "FB_TOKEN",
+            "FB_TOKEN_FILE"
+        ]
+    );
+}
+
+#[test]
+fn fallbacks_unknown_vars() {
+    std::env::set_var("FB_TOKEN_FILE", "/nonexistent");
+    std::env::set_var("FB_PORTS", "3");
+    let unknown: Vec<String> = unknown_vars(FLAGS, &["FB_"])
+        .into_iter()
+        .map(|x| x.key)
+        .collect();
+    assert_eq!(unknown, ["FB_PORTS"]);
+}
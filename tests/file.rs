@@ -0,0 +1,105 @@
+use rs_envflag::strict::unknown_vars;
+use rs_envflag::{Flag, Source};
+use rs_envflag_macros::*;
+
+mod common;
+use common::{temp_file, Global};
+
+#[test]
+fn allow_file() {
+    #[envflag(allow_file, secret)]
+    const FILE_DB_PASSWORD: Option<String>;
+
+    let mut global = Global::lock();
+    let password = temp_file("password", "s3cret\n");
+    assert_eq!(FILE_DB_PASSWORD.fetch().unwrap(), None);
+    global.set_var("FILE_DB_PASSWORD_FILE", &password);
+    assert_eq!(
+        FILE_DB_PASSWORD.fetch_with_source().unwrap(),
+        Some((
+            "s3cret".to_string(),
+            Source::File {
+                path: password.clone(),
+                key: "FILE_DB_PASSWORD".to_string(),
+            }
+        ))
+    );
+    std::fs::remove_file(&password).unwrap();
+}
+
+#[test]
+fn allow_file_both_set() {
+    #[envflag(allow_file)]
+    const FILE_BOTH: Option<String>;
+
+    let mut global = Global::lock();
+    global.set_var("FILE_BOTH", "plain");
+    global.set_var("FILE_BOTH_FILE", "/nonexistent");
+    assert_eq!(
+        FILE_BOTH.fetch().unwrap_err().to_string(),
+        "Both ENV FILE_BOTH and FILE_BOTH_FILE are set"
+    );
+}
+
+#[test]
+fn allow_file_trims_one_newline() {
+    #[envflag(allow_file, default = 1)]
+    const FILE_REPLICAS: i64;
+
+    let mut global = Global::lock();
+    // Only one trailing newline is trimmed, and the content goes through the parser.
+    let replicas = temp_file("replicas", "3\r\n\n");
+    global.set_var("FILE_REPLICAS_FILE", &replicas);
+    assert!(FILE_REPLICAS.fetch().is_err());
+    std::fs::write(&replicas, "3\n").unwrap();
+    assert_eq!(FILE_REPLICAS.fetch().unwrap(), 3);
+    std::fs::remove_file(&replicas).unwrap();
+}
+
+#[test]
+fn allow_file_missing() {
+    #[envflag(allow_file, default = 1)]
+    const FILE_MISSING: i64;
+
+    let mut global = Global::lock();
+    global.set_var(
+        "FILE_MISSING_FILE",
+        std::env::temp_dir().join("rs_envflag_missing"),
+    );
+    assert!(FILE_MISSING
+        .fetch()
+        .unwrap_err()
+        .to_string()
+        .starts_with("Fail to read"));
+}
+
+#[test]
+fn allow_file_keys() {
+    #[envflag(allow_file, default = 1)]
+    const FILE_KEYS: i64;
+
+    assert_eq!(FILE_KEYS.keys(), ["FILE_KEYS", "FILE_KEYS_FILE"]);
+}
+
+#[test]
+fn without_allow_file() {
+    #[envflag(allow_file, default = 1)]
+    const FILE_UNKNOWN_REPLICAS: i64;
+
+    #[envflag(default = 1)]
+    const FILE_UNKNOWN_WORKERS: i64;
+
+    let mut global = Global::lock();
+    // Without `allow_file`, `_FILE` is not a flag.
+    let workers = temp_file("workers", "3\n");
+    global.set_var("FILE_UNKNOWN_REPLICAS_FILE", &workers);
+    global.set_var("FILE_UNKNOWN_WORKERS_FILE", &workers);
+    assert_eq!(FILE_UNKNOWN_WORKERS.fetch().unwrap(), 1);
+    let flags: &[&dyn Flag] = &[&FILE_UNKNOWN_REPLICAS, &FILE_UNKNOWN_WORKERS];
+    let unknown: Vec<String> = unknown_vars(flags, &["FILE_UNKNOWN_"])
+        .into_iter()
+        .map(|x| x.key)
+        .collect();
+    assert_eq!(unknown, ["FILE_UNKNOWN_WORKERS_FILE"]);
+    std::fs::remove_file(&workers).unwrap();
+}
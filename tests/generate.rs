@@ -0,0 +1,468 @@
+use rs_envflag::generate::{env_example, json_schema, man, markdown};
+use rs_envflag::{DotEnv, Flag};
+use rs_envflag_macros::*;
+
+/// the port to listen on
+#[envflag(default = 8080)]
+const GEN_PORT: i64;
+
+/// the greeting
+///
+/// It can span multiple lines.
+#[envflag(default = "hello, world")]
+const GEN_GREETING: String;
+
+#[envflag(parser=secs_parser, default_str="30s")]
+const GEN_TIMEOUT: std::time::Duration;
+
+#[envflag(default_fn = workers)]
+const GEN_WORKERS: i64;
+
+/// the password of the database
+#[envflag(secret)]
+const GEN_PASSWORD: Option<String>;
+
+#[envflag(default = -1)]
+const GEN_NEGATIVE: i64;
+
+const FLAGS: &[&dyn Flag] = &[
+    &GEN_PORT,
+    &GEN_GREETING,
+    &GEN_TIMEOUT,
+    &GEN_WORKERS,
+    &GEN_PASSWORD,
+    &GEN_NEGATIVE,
+];
+
+#[test]
+fn meta() {
+    let meta = GEN_GREETING.env.meta;
+    assert_eq!(
+        meta.doc_lines().collect::<Vec<_>>(),
+        ["the greeting", "", "It can span multiple lines."]
+    );
+    assert_eq!(meta.type_name, "String");
+    assert_eq!(meta.default, Some("hello, world"));
+    assert_eq!(GEN_TIMEOUT.env.meta.type_name, "std::time::Duration");
+    assert_eq!(GEN_TIMEOUT.env.meta.default, Some("30s"));
+    assert_eq!(GEN_WORKERS.env.meta.default, None);
+    assert_eq!(GEN_NEGATIVE.env.meta.default, Some("-1"));
+    assert!(GEN_PASSWORD.meta().secret);
+}
+
+#[test]
+fn dot_env_example() {
+    let res = env_example(FLAGS);
+    assert_eq!(
+        res,
+        r#"# the port to listen on
+# type: i64, default: 8080
+GEN_PORT=8080
+
+# the greeting
+#
+# It can span multiple lines.
+# type: String, default: hello, world
+GEN_GREETING="hello, world"
+
+# type: std::time::Duration, default: 30s
+GEN_TIMEOUT=30s
+
+# type: i64, defaulted
+# GEN_WORKERS=
+
+# the password of the database
+# type: String, optional, secret
+# GEN_PASSWORD=
+
+# type: i64, default: -1
+GEN_NEGATIVE=-1
+"#
+    );
+    let dotenv = DotEnv::parse(".env.example", &res).unwrap();
+    assert_eq!(dotenv.get("GEN_GREETING"), Some("hello, world"));
+    assert_eq!(dotenv.get("GEN_PORT"), Some("8080"));
+}
+
+#[test]
+fn dot_env_example_falls_back_to_defaults() {
+    let dotenv = DotEnv::parse(".env.example", &env_example(FLAGS)).unwrap();
+    assert_eq!(GEN_WORKERS.fetch_from(&dotenv).unwrap(), 4);
+    assert_eq!(GEN_PASSWORD.fetch_from(&dotenv).unwrap(), None);
+}
+
+#[test]
+fn schema() {
+    let res = json_schema(FLAGS);
+    let schema: serde_json::Value = serde_json::from_str(&res).unwrap();
+    assert_eq!(schema["type"], "object");
+    let props = &schema["properties"];
+    assert_eq!(
+        props["GEN_PORT"],
+        serde_json::json!({
+            "type": "string",
+            "pattern": "^[+-]?[0-9]+$",
+            "description": "the port to listen on",
+            "default": "8080",
+        })
+    );
+    assert_eq!(
+        props["GEN_GREETING"],
+        serde_json::json!({
+            "type": "string",
+            "description": "the greeting\n\nIt can span multiple lines.",
+            "default": "hello, world",
+        })
+    );
+    assert_eq!(
+        props["GEN_TIMEOUT"],
+        serde_json::json!({"type": "string", "default": "30s"})
+    );
+    assert_eq!(
+        props["GEN_WORKERS"],
+        serde_json::json!({"type": "string", "pattern": "^[+-]?[0-9]+$"})
+    );
+    assert_eq!(
+        props["GEN_PASSWORD"],
+        serde_json::json!({
+            "type": "string",
+            "description": "the password of the database",
+            "writeOnly": true,
+        })
+    );
+    assert_eq!(props["GEN_NEGATIVE"]["default"], "-1");
+
+    assert_eq!(schema["required"], serde_json::json!([]));
+
+    let empty: serde_json::Value = serde_json::from_str(&json_schema(&[])).unwrap();
+    assert_eq!(empty["properties"], serde_json::json!({}));
+    assert_eq!(empty["required"], serde_json::json!([]));
+}
+
+#[test]
+fn schema_constraints() {
+    #[envflag(parser = small_parser, default_str = "8")]
+    const GEN_SMALL: u8;
+
+    #[envflag(parser = smalls_parser)]
+    const GEN_SMALLS: Option<Vec<i16>>;
+
+    fn small_parser(_key: &str, val: &str) -> anyhow::Result<u8> {
+        Ok(val.parse()?)
+    }
+
+    fn smalls_parser(_key: &str, val: &str) -> anyhow::Result<Vec<i16>> {
+        Ok(val
+            .split(',')
+            .map(|x| x.parse())
+            .collect::<Result<_, _>>()?)
+    }
+
+    #[envflag(default_str = "10%")]
+    const GEN_ROLLOUT: rs_envflag::Rollout;
+
+    #[envflag(default_str = "control:50,treatment:50")]
+    const GEN_VARIANTS: rs_envflag::Variants;
+
+    #[envflag(default = true)]
+    const GEN_ENABLED: bool;
+
+    #[envflag(default = 0.5)]
+    const GEN_RATIO: f64;
+
+    #[envflag(parser = rs_envflag::variants_parser)]
+    const GEN_ANY_VARIANTS: Option<rs_envflag::Variants>;
+
+    let res = json_schema(&[
+        &GEN_SMALL,
+        &GEN_SMALLS,
+        &GEN_ROLLOUT,
+        &GEN_VARIANTS,
+        &GEN_ENABLED,
+        &GEN_RATIO,
+        &GEN_ANY_VARIANTS,
+    ]);
+    let schema: serde_json::Value = serde_json::from_str(&res).unwrap();
+    let props = &schema["properties"];
+    assert_eq!(
+        props["GEN_SMALL"],
+        serde_json::json!({"type": "string", "pattern": "^\\+?[0-9]+$", "default": "8"})
+    );
+    assert_eq!(props["GEN_SMALLS"], serde_json::json!({"type": "string"}));
+
+    let matches = |flag: &str, x: &str| {
+        let pattern = props[flag]["pattern"].as_str().unwrap();
+        regex_lite::Regex::new(pattern).unwrap().is_match(x)
+    };
+    for x in ["true", "false"] {
+        assert!(matches("GEN_ENABLED", x), "{}", x);
+    }
+    for x in ["1", "yes", "True", ""] {
+        assert!(!matches("GEN_ENABLED", x), "{}", x);
+    }
+    for x in [
+        "0.5",
+        "-1",
+        "+1.",
+        ".5",
+        "1e-3",
+        "2.5E+10",
+        "inf",
+        "-infinity",
+        "NaN",
+    ] {
+        assert!(matches("GEN_RATIO", x), "{}", x);
+        assert!(x.parse::<f64>().is_ok(), "{}", x);
+    }
+    for x in ["", ".", "1,5", "e3", "0x10", " 1"] {
+        assert!(!matches("GEN_RATIO", x), "{}", x);
+        assert!(x.parse::<f64>().is_err(), "{}", x);
+    }
+    for x in ["8", "+8", "300"] {
+        assert!(matches("GEN_SMALL", x), "{}", x);
+    }
+    for x in ["-8", "8.0", ""] {
+        assert!(!matches("GEN_SMALL", x), "{}", x);
+    }
+    assert_eq!(props["GEN_ROLLOUT"]["type"], "string");
+    for x in ["25%", "12.5%", "100", "0", "true", " 50 % "] {
+        assert!(matches("GEN_ROLLOUT", x), "{}", x);
+    }
+    for x in ["101%", "-1%", "half", "true%"] {
+        assert!(!matches("GEN_ROLLOUT", x), "{}", x);
+    }
+    assert_eq!(props["GEN_VARIANTS"]["type"], "string");
+    for x in [
+        "control:50,treatment:50",
+        "treatment:100",
+        "control: 50, treatment : 50",
+    ] {
+        assert!(matches("GEN_VARIANTS", x), "{}", x);
+    }
+    for x in ["control", "control:50,", ":100", "control:x", "a:100"] {
+        assert!(!matches("GEN_VARIANTS", x), "{}", x);
+    }
+    assert!(props["GEN_VARIANTS"].get("enum").is_none());
+    for x in ["control:50,treatment:50", "a:100", "a: 50, b : 50"] {
+        assert!(matches("GEN_ANY_VARIANTS", x), "{}", x);
+    }
+    for x in ["control", "a:50,", ":100", "a:x"] {
+        assert!(!matches("GEN_ANY_VARIANTS", x), "{}", x);
+    }
+}
+
+/// the name of the service
+#[envflag]
+const GEN_SERVICE: String;
+
+#[envflag(variants = ["debug", "info", "warn"], default = "info")]
+const GEN_LOG_LEVEL: String;
+
+#[envflag(env_name = "GEN_SHARD_{}_HOST")]
+const GEN_SHARD_HOST: String;
+
+const REQUIRED_FLAGS: &[&dyn Flag] = &[&GEN_SERVICE, &GEN_LOG_LEVEL, &GEN_SHARD_HOST];
+
+#[test]
+fn required() {
+    assert!(GEN_SERVICE.required());
+    assert!(GEN_SHARD_HOST.required());
+    assert!(!GEN_LOG_LEVEL.required());
+    assert!(!GEN_PASSWORD.required());
+    assert_eq!(GEN_LOG_LEVEL.env.meta.variants, ["debug", "info", "warn"]);
+
+    let dotenv = DotEnv::parse(".env", "GEN_SHARD_1_HOST=a").unwrap();
+    assert_eq!(
+        GEN_SERVICE.fetch_from(&dotenv).unwrap_err().to_string(),
+        "Missing ENV \"GEN_SERVICE\""
+    );
+    assert_eq!(GEN_SHARD_HOST.fetch_for_from("1", &dotenv).unwrap(), "a");
+    assert!(GEN_SHARD_HOST.fetch_for_from("2", &dotenv).is_err());
+    let dotenv = DotEnv::parse(".env", "GEN_SERVICE=api").unwrap();
+    assert_eq!(GEN_SERVICE.fetch_from(&dotenv).unwrap(), "api");
+}
+
+#[test]
+fn schema_of_required_flags() {
+    let res = json_schema(REQUIRED_FLAGS);
+    let schema: serde_json::Value = serde_json::from_str(&res).unwrap();
+    assert_eq!(schema["required"], serde_json::json!(["GEN_SERVICE"]));
+    assert_eq!(
+        schema["properties"]["GEN_LOG_LEVEL"],
+        serde_json::json!({
+            "type": "string",
+            "enum": ["debug", "info", "warn"],
+            "default": "info",
+        })
+    );
+}
+
+#[test]
+fn documents_of_required_flags() {
+    assert_eq!(
+        env_example(REQUIRED_FLAGS),
+        r#"# the name of the service
+# type: String, required
+# GEN_SERVICE=
+
+# type: String, default: info
+GEN_LOG_LEVEL=info
+
+# type: String, required
+# GEN_SHARD_<INDEX>_HOST=
+"#
+    );
+    assert_eq!(
+        markdown(REQUIRED_FLAGS),
+        r#"| Variable | Type | Default | Description |
+| --- | --- | --- | --- |
+| `GEN_SERVICE` | `String` | required | the name of the service |
+| `GEN_LOG_LEVEL` | `String` | `info` |  |
+| `GEN_SHARD_<INDEX>_HOST` | `String` | required |  |
+"#
+    );
+    assert!(man(REQUIRED_FLAGS)
+        .contains(".B GEN_SERVICE\nthe name of the service\n.br\nType: String. Required.\n"));
+}
+
+#[test]
+fn markdown_table() {
+    assert_eq!(
+        markdown(FLAGS),
+        r#"| Variable | Type | Default | Description |
+| --- | --- | --- | --- |
+| `GEN_PORT` | `i64` | `8080` | the port to listen on |
+| `GEN_GREETING` | `String` | `hello, world` | the greeting<br><br>It can span multiple lines. |
+| `GEN_TIMEOUT` | `std::time::Duration` | `30s` |  |
+| `GEN_WORKERS` | `i64` | computed |  |
+| `GEN_PASSWORD` | `String` | secret | the password of the database |
+| `GEN_NEGATIVE` | `i64` | `-1` |  |
+"#
+    );
+}
+
+#[test]
+fn man_page() {
+    assert_eq!(
+        man(FLAGS),
+        r#".SH ENVIRONMENT
+.TP
+.B GEN_PORT
+the port to listen on
+.br
+Type: i64. Default: 8080.
+.TP
+.B GEN_GREETING
+the greeting
+.IP
+It can span multiple lines.
+.br
+Type: String. Default: hello, world.
+.TP
+.B GEN_TIMEOUT
+Type: std::time::Duration. Default: 30s.
+.TP
+.B GEN_WORKERS
+Type: i64. Default: computed.
+.TP
+.B GEN_PASSWORD
+the password of the database
+.br
+Type: String. Secret.
+.TP
+.B GEN_NEGATIVE
+Type: i64. Default: \-1.
+"#
+    );
+}
+
+fn secs_parser(key: &str, value: &str) -> anyhow::Result<std::time::Duration> {
+    let Some(secs) = value.strip_suffix('s') else {
+        anyhow::bail!("Unrecognized ENV \"{}\": \"{}\"", key, value);
+    };
+    Ok(std::time::Duration::from_secs(secs.parse()?))
+}
+
+fn workers() -> anyhow::Result<i64> {
+    Ok(4)
+}
+
+/// databases
+#[envflag(indexed)]
+const GEN_DATABASES: Vec<String>;
+
+#[envflag(prefix = "GEN_FEATURE_")]
+const GEN_FEATURES: std::collections::BTreeMap<String, bool>;
+
+#[envflag]
+const GEN_SEARCH_PATH: Vec<std::path::PathBuf>;
+
+#[envflag(env_name = "GEN_SHARD_{}_WEIGHT", default = 1)]
+const GEN_SHARD_WEIGHT: i64;
+
+const PATTERN_FLAGS: &[&dyn Flag] = &[
+    &GEN_DATABASES,
+    &GEN_FEATURES,
+    &GEN_SEARCH_PATH,
+    &GEN_SHARD_WEIGHT,
+];
+
+#[test]
+fn dot_env_example_of_patterns() {
+    let res = env_example(PATTERN_FLAGS);
+    assert_eq!(
+        res,
+        r#"# databases
+# type: Vec<String>, optional
+# GEN_DATABASES_<N>=
+
+# type: std::collections::BTreeMap<String, bool>, optional
+# GEN_FEATURE_<NAME>=
+
+# type: Vec<std::path::PathBuf>, optional, separated by :
+# GEN_SEARCH_PATH=
+
+# type: i64, default: 1
+# GEN_SHARD_<INDEX>_WEIGHT=
+"#
+    );
+    let dotenv = DotEnv::parse(".env.example", &res).unwrap();
+    assert_eq!(dotenv.get("GEN_SEARCH_PATH"), None);
+    assert_eq!(dotenv.get("GEN_DATABASES"), None);
+}
+
+#[test]
+fn schema_of_patterns() {
+    let res = json_schema(PATTERN_FLAGS);
+    let schema: serde_json::Value = serde_json::from_str(&res).unwrap();
+    assert_eq!(
+        schema["properties"],
+        serde_json::json!({"GEN_SEARCH_PATH": {"type": "string"}})
+    );
+    assert_eq!(
+        schema["patternProperties"],
+        serde_json::json!({
+            "^GEN_DATABASES_(0|[1-9][0-9]*)$": {"type": "string", "description": "databases"},
+            "^GEN_FEATURE_.+$": {"type": "string", "pattern": "^(true|false)$"},
+            "^GEN_SHARD_.+_WEIGHT$": {
+                "type": "string",
+                "pattern": "^[+-]?[0-9]+$",
+                "default": "1",
+            },
+        })
+    );
+}
+
+#[test]
+fn markdown_table_of_patterns() {
+    assert_eq!(
+        markdown(PATTERN_FLAGS),
+        r#"| Variable | Type | Default | Description |
+| --- | --- | --- | --- |
+| `GEN_DATABASES_<N>` | `Vec<String>` | - | databases |
+| `GEN_FEATURE_<NAME>` | `std::collections::BTreeMap<String, bool>` | - |  |
+| `GEN_SEARCH_PATH` | `Vec<std::path::PathBuf>` | - |  |
+| `GEN_SHARD_<INDEX>_WEIGHT` | `i64` | `1` |  |
+"#
+    );
+}
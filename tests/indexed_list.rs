@@ -0,0 +1,158 @@
+use rs_envflag::{Flag, Source};
+use rs_envflag_macros::*;
+
+mod common;
+use common::dotenv;
+
+#[envflag(indexed)]
+const IL_DATABASES: Vec<String>;
+
+#[envflag(indexed = 2)]
+const IL_PORTS: Vec<i64>;
+
+fn databases_err(content: &str) -> String {
+    format!(
+        "{:#}",
+        IL_DATABASES.fetch_from(&dotenv(content)).unwrap_err()
+    )
+}
+
+fn ports_err(content: &str) -> String {
+    format!("{:#}", IL_PORTS.fetch_from(&dotenv(content)).unwrap_err())
+}
+
+#[test]
+fn indexed_list() {
+    let layer = dotenv(
+        r#"
+IL_DATABASES_0="postgres://a/db?x=1,y=2"
+IL_DATABASES_1=mysql://b/db
+"#,
+    );
+    assert_eq!(
+        IL_DATABASES.fetch_from(&layer).unwrap(),
+        ["postgres://a/db?x=1,y=2", "mysql://b/db"]
+    );
+    assert_eq!(
+        IL_DATABASES.fetch_from_with_source(&layer).unwrap()[1].1,
+        Source::File {
+            path: "test.env".into(),
+            key: "IL_DATABASES_1".to_string(),
+        }
+    );
+}
+
+#[test]
+fn indexed_list_empty() {
+    assert!(IL_DATABASES.fetch_from(&dotenv("")).unwrap().is_empty());
+}
+
+#[test]
+fn indexed_list_meta() {
+    assert_eq!(IL_PORTS.meta().type_name, "Vec<i64>");
+}
+
+#[test]
+fn indexed_list_gap() {
+    assert_eq!(
+        databases_err("IL_DATABASES_0=a\nIL_DATABASES_2=c"),
+        "Gap in ENV IL_DATABASES_*: IL_DATABASES_1 is missing but IL_DATABASES_2 is set"
+    );
+}
+
+#[test]
+fn indexed_list_missing_first() {
+    assert_eq!(
+        databases_err("IL_DATABASES_1=b"),
+        "Gap in ENV IL_DATABASES_*: IL_DATABASES_0 is missing but IL_DATABASES_1 is set"
+    );
+}
+
+#[test]
+fn indexed_list_far_gap_in_env() {
+    #[envflag(indexed)]
+    const IL_FAR: Vec<String>;
+
+    std::env::set_var("IL_FAR_0", "a");
+    std::env::set_var("IL_FAR_20", "b");
+    assert_eq!(
+        format!("{:#}", IL_FAR.fetch().unwrap_err()),
+        "Gap in ENV IL_FAR_*: IL_FAR_1 is missing but IL_FAR_20 is set"
+    );
+}
+
+#[test]
+fn indexed_list_duplicate() {
+    assert_eq!(
+        databases_err("IL_DATABASES_0=a\nIL_DATABASES_1=b\nIL_DATABASES_01=c"),
+        "Duplicate ENV IL_DATABASES_1 and IL_DATABASES_01"
+    );
+}
+
+#[test]
+fn indexed_list_leading_zeros() {
+    assert_eq!(
+        databases_err("IL_DATABASES_0=a\nIL_DATABASES_01=b\nIL_DATABASES_02=c"),
+        "Invalid ENV IL_DATABASES_01: indices of IL_DATABASES_* cannot have leading zeros"
+    );
+    assert_eq!(
+        databases_err("IL_DATABASES_0=a\nIL_DATABASES_05=b"),
+        "Invalid ENV IL_DATABASES_05: indices of IL_DATABASES_* cannot have leading zeros"
+    );
+}
+
+#[test]
+fn indexed_list_leading_zeros_in_env() {
+    #[envflag(indexed)]
+    const IL_PADDED: Vec<String>;
+
+    std::env::set_var("IL_PADDED_0", "a");
+    std::env::set_var("IL_PADDED_1", "b");
+    std::env::set_var("IL_PADDED_001", "c");
+    assert_eq!(
+        format!("{:#}", IL_PADDED.fetch().unwrap_err()),
+        "Invalid ENV IL_PADDED_001: indices of IL_PADDED_* cannot have leading zeros"
+    );
+    assert_eq!(IL_PADDED.keys(), ["IL_PADDED_0", "IL_PADDED_1"]);
+}
+
+#[test]
+fn indexed_list_too_many() {
+    assert_eq!(
+        ports_err("IL_PORTS_0=1\nIL_PORTS_1=2\nIL_PORTS_2=3"),
+        "Too many ENV IL_PORTS_*: IL_PORTS_2 is set but at most 2 items are allowed"
+    );
+    assert_eq!(
+        ports_err("IL_PORTS_0=1\nIL_PORTS_1=2\nIL_PORTS_5=3"),
+        "Too many ENV IL_PORTS_*: IL_PORTS_5 is set but at most 2 items are allowed"
+    );
+    assert_eq!(
+        IL_PORTS
+            .fetch_from(&dotenv("IL_PORTS_0=1\nIL_PORTS_1=2"))
+            .unwrap(),
+        [1, 2]
+    );
+}
+
+#[test]
+fn indexed_list_invalid_item() {
+    assert!(ports_err("IL_PORTS_0=1\nIL_PORTS_1=x")
+        .starts_with("Fail to parse item 1 of ENV IL_PORTS_*: Unrecognized ENV \"IL_PORTS_1\""));
+}
+
+#[test]
+fn indexed_list_env() {
+    #[envflag(indexed = 2)]
+    const IL_ENV_PORTS: Vec<i64>;
+
+    std::env::set_var("IL_ENV_PORTS_0", "80");
+    std::env::set_var("IL_ENV_PORTS_00", "80");
+    assert_eq!(IL_ENV_PORTS.keys(), ["IL_ENV_PORTS_0"]);
+    let diagnosis = IL_ENV_PORTS.diagnose();
+    assert_eq!(
+        diagnosis.error.as_deref(),
+        Some("Duplicate ENV IL_ENV_PORTS_0 and IL_ENV_PORTS_00")
+    );
+    std::env::remove_var("IL_ENV_PORTS_00");
+    assert_eq!(IL_ENV_PORTS.diagnose().value.as_deref(), Some("[80]"));
+}
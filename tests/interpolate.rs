@@ -0,0 +1,89 @@
+use rs_envflag_macros::*;
+
+mod common;
+use common::dotenv;
+
+#[envflag(interpolate)]
+const LOG_DIR: Option<String>;
+
+#[envflag(interpolate, default = "http://localhost")]
+const URL: String;
+
+#[envflag(interpolate, default = 1)]
+const REPLICAS: i64;
+
+#[envflag]
+const RAW: Option<String>;
+
+#[test]
+fn interpolate() {
+    let layer = dotenv(
+        r#"
+DATA_DIR=/var/lib/app
+LOG_DIR=${DATA_DIR}/logs
+HOST=example.com
+URL=http://${HOST}:${PORT:-8080}/$${HOST}/$HOST
+REPLICAS=${N:-${DEFAULT_N}}
+DEFAULT_N=3
+RAW=${DATA_DIR}
+"#,
+    );
+    assert_eq!(
+        LOG_DIR.fetch_from(&layer).unwrap().as_deref(),
+        Some("/var/lib/app/logs")
+    );
+    assert_eq!(
+        URL.fetch_from(&layer).unwrap(),
+        "http://example.com:8080/${HOST}/$HOST"
+    );
+    assert_eq!(REPLICAS.fetch_from(&layer).unwrap(), 3);
+    // Interpolation is opt-in.
+    assert_eq!(
+        RAW.fetch_from(&layer).unwrap().as_deref(),
+        Some("${DATA_DIR}")
+    );
+
+    // Unset variables are empty, and defaults apply to empty ones too.
+    let layer = dotenv("LOG_DIR=${DATA_DIR}/logs\nPORT=\nURL=http://h:${PORT:-80}");
+    assert_eq!(
+        LOG_DIR.fetch_from(&layer).unwrap().as_deref(),
+        Some("/logs")
+    );
+    assert_eq!(URL.fetch_from(&layer).unwrap(), "http://h:80");
+
+    // Values of referenced variables are expanded too.
+    let layer = dotenv("A=${B}/a\nB=${C}/b\nC=c\nLOG_DIR=${A}");
+    assert_eq!(
+        LOG_DIR.fetch_from(&layer).unwrap().as_deref(),
+        Some("c/b/a")
+    );
+}
+
+#[test]
+fn errors() {
+    let err = |content: &str| {
+        LOG_DIR
+            .fetch_from(&dotenv(content))
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        err("LOG_DIR=${A}\nA=${B}\nB=${A}"),
+        "Cyclic interpolation among ENVs: A -> B -> A"
+    );
+    assert_eq!(
+        err("LOG_DIR=${LOG_DIR}"),
+        "Cyclic interpolation among ENVs: LOG_DIR -> LOG_DIR"
+    );
+    assert_eq!(err("LOG_DIR=${A"), "Unterminated \"${\" in ENV LOG_DIR");
+    assert_eq!(
+        err("LOG_DIR=${A B}"),
+        "Invalid variable \"A B\" in ENV LOG_DIR"
+    );
+    let chain: String = (0..20)
+        .map(|i| format!("V{}=${{V{}}}\n", i, i + 1))
+        .collect();
+    assert!(err(&format!("LOG_DIR=${{V0}}\n{}", chain))
+        .starts_with("Too deep interpolation of ENV LOG_DIR"));
+    assert!(REPLICAS.fetch_from(&dotenv("REPLICAS=${N}")).is_err());
+}
@@ -0,0 +1,157 @@
+#[cfg(any(feature = "json", feature = "toml"))]
+use rs_envflag::ConfigFile;
+use rs_envflag::{Args, Env, Layer, Resolver};
+use rs_envflag_macros::*;
+
+mod common;
+use common::Global;
+
+#[test]
+fn args() {
+    let args = Args::new([
+        "positional",
+        "--metrics-port=9090",
+        "--LOG_LEVEL=debug",
+        "--flag",
+        "--",
+        "--after=1",
+    ]);
+    assert_eq!(args.get("METRICS_PORT").unwrap(), Some("9090".to_string()));
+    assert_eq!(args.get("LOG_LEVEL").unwrap(), Some("debug".to_string()));
+    assert_eq!(args.get("FLAG").unwrap(), None);
+    assert_eq!(args.get("AFTER").unwrap(), None);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn config_file_toml() {
+    let config =
+        ConfigFile::parse_toml("app.toml", "S = \"abc\"\nI = 42\nF = 4.2\nB = true\n").unwrap();
+    assert_eq!(config.get("S").unwrap(), Some("abc".to_string()));
+    assert_eq!(config.get("I").unwrap(), Some("42".to_string()));
+    assert_eq!(config.get("F").unwrap(), Some("4.2".to_string()));
+    assert_eq!(config.get("B").unwrap(), Some("true".to_string()));
+    assert_eq!(config.get("ABSENT").unwrap(), None);
+
+    assert!(ConfigFile::parse_toml("app.toml", "[T]\nX = 1\n").is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn config_file_json() {
+    let config =
+        ConfigFile::parse_json("app.json", r#"{"S": "abc", "I": 42, "F": 4.2, "B": false}"#)
+            .unwrap();
+    assert_eq!(config.get("S").unwrap(), Some("abc".to_string()));
+    assert_eq!(config.get("I").unwrap(), Some("42".to_string()));
+    assert_eq!(config.get("F").unwrap(), Some("4.2".to_string()));
+    assert_eq!(config.get("B").unwrap(), Some("false".to_string()));
+
+    assert!(ConfigFile::parse_json("app.json", r#"{"L": [1]}"#).is_err());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn precedence() {
+    #[envflag(default = 1)]
+    const LAYERED_ARG: i64;
+
+    #[envflag(default = 1)]
+    const LAYERED_ENV: i64;
+
+    #[envflag(default = 1)]
+    const LAYERED_FILE: i64;
+
+    #[envflag(default = 1)]
+    const LAYERED_DEFAULT: i64;
+
+    let mut global = Global::lock();
+    global.set_var("LAYERED_ARG", "3");
+    global.set_var("LAYERED_ENV", "3");
+    let resolver = Resolver::new()
+        .layer(Args::new(["--layered-arg=4"]))
+        .layer(Env)
+        .layer(
+            ConfigFile::parse_toml(
+                "app.toml",
+                "LAYERED_ARG = 2\nLAYERED_ENV = 2\nLAYERED_FILE = 2\n",
+            )
+            .unwrap(),
+        );
+    assert_eq!(LAYERED_ARG.fetch_from(&resolver).unwrap(), 4);
+    assert_eq!(LAYERED_ENV.fetch_from(&resolver).unwrap(), 3);
+    assert_eq!(LAYERED_FILE.fetch_from(&resolver).unwrap(), 2);
+    assert_eq!(LAYERED_DEFAULT.fetch_from(&resolver).unwrap(), 1);
+}
+
+#[test]
+fn install() {
+    #[envflag]
+    const INSTALLED_FLAG: Option<String>;
+
+    let _global = Global::lock();
+    assert_eq!(INSTALLED_FLAG.fetch().unwrap(), None);
+    Resolver::new()
+        .layer(Args::new(["--installed-flag=xixi"]))
+        .layer(Env)
+        .install();
+    assert_eq!(INSTALLED_FLAG.fetch().unwrap(), Some("xixi".to_string()));
+    Resolver::uninstall();
+    assert_eq!(INSTALLED_FLAG.fetch().unwrap(), None);
+}
+
+#[test]
+fn provenance() {
+    use rs_envflag::{DotEnv, Source};
+
+    #[envflag(default = 1)]
+    const SOURCE_ARG: i64;
+
+    #[envflag(default = 1)]
+    const SOURCE_ENV: i64;
+
+    #[envflag(default = 1)]
+    const SOURCE_FILE: i64;
+
+    #[envflag(default = 1)]
+    const SOURCE_DEFAULT: i64;
+
+    #[envflag]
+    const SOURCE_ABSENT: Option<i64>;
+
+    let mut global = Global::lock();
+    global.set_var("SOURCE_ENV", "3");
+    let resolver = Resolver::new()
+        .layer(Args::new(["--source-arg=4"]))
+        .layer(Env)
+        .layer(DotEnv::parse("app.env", "SOURCE_FILE=2\n").unwrap());
+    assert_eq!(
+        SOURCE_ARG.fetch_from_with_source(&resolver).unwrap(),
+        (
+            4,
+            Source::Arg {
+                arg: "--source-arg".to_string()
+            }
+        )
+    );
+    assert_eq!(
+        SOURCE_ENV.fetch_from_with_source(&resolver).unwrap(),
+        (
+            3,
+            Source::Env {
+                key: "SOURCE_ENV".to_string()
+            }
+        )
+    );
+    let (value, source) = SOURCE_FILE.fetch_from_with_source(&resolver).unwrap();
+    assert_eq!(value, 2);
+    assert_eq!(source.to_string(), "SOURCE_FILE in app.env");
+    assert_eq!(
+        SOURCE_DEFAULT.fetch_from_with_source(&resolver).unwrap(),
+        (1, Source::Default { profile: None })
+    );
+    assert_eq!(
+        SOURCE_ABSENT.fetch_from_with_source(&resolver).unwrap(),
+        None
+    );
+}
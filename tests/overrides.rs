@@ -0,0 +1,50 @@
+use rs_envflag::{Flag, Resolver, Source};
+use rs_envflag_macros::*;
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::{dotenv, Global};
+
+#[envflag(default = 4)]
+const OVERRIDE_POOL_SIZE: i64;
+
+#[envflag]
+const OVERRIDE_NAME: Option<String>;
+
+#[test]
+fn overrides() {
+    let mut global = Global::lock();
+    global.defer(|| OVERRIDE_POOL_SIZE.clear_override());
+    global.defer(|| OVERRIDE_NAME.clear_override());
+    let changes = Arc::new(Mutex::new(vec![]));
+    let _sub = {
+        let changes = changes.clone();
+        OVERRIDE_POOL_SIZE.subscribe(move |old, new| changes.lock().unwrap().push((*old, *new)))
+    };
+    Resolver::new()
+        .layer(dotenv("OVERRIDE_POOL_SIZE=8"))
+        .install();
+
+    OVERRIDE_POOL_SIZE.set_override(16).unwrap();
+    assert_eq!(
+        OVERRIDE_POOL_SIZE.fetch_with_source().unwrap(),
+        (16, Source::Override)
+    );
+    let diagnosis = OVERRIDE_POOL_SIZE.diagnose();
+    assert_eq!(diagnosis.value.as_deref(), Some("16"));
+    assert_eq!(diagnosis.source, Some(Source::Override));
+    assert_eq!(diagnosis.source.unwrap().to_string(), "override");
+
+    // Invalid values are rejected.
+    assert!(OVERRIDE_POOL_SIZE.set_override("many").is_err());
+    assert_eq!(OVERRIDE_POOL_SIZE.fetch().unwrap(), 16);
+
+    OVERRIDE_POOL_SIZE.clear_override();
+    assert_eq!(OVERRIDE_POOL_SIZE.fetch().unwrap(), 8);
+    assert_eq!(*changes.lock().unwrap(), vec![(4, 8), (8, 16), (16, 8)]);
+
+    OVERRIDE_NAME.set_override("a").unwrap();
+    assert_eq!(OVERRIDE_NAME.fetch().unwrap().as_deref(), Some("a"));
+    OVERRIDE_NAME.clear_override();
+    assert_eq!(OVERRIDE_NAME.fetch().unwrap(), None);
+}
@@ -0,0 +1,123 @@
+#![cfg(unix)]
+
+use rs_envflag::{DotEnv, Flag, Source};
+use rs_envflag_macros::*;
+use std::path::PathBuf;
+
+/// The declared type is what `fetch()` returns, so importing it is not an unused import.
+mod declared {
+    #![deny(unused_imports)]
+
+    use rs_envflag_macros::*;
+    use std::path::PathBuf;
+
+    #[envflag]
+    pub const PL_DECLARED: Vec<PathBuf>;
+}
+
+#[envflag]
+const PL_SEARCH_PATH: Vec<PathBuf>;
+
+#[envflag(env_name = "PL_PLUGIN_PATH", empty_paths = "error")]
+const PLUGIN_PATH: Vec<std::path::PathBuf>;
+
+#[envflag(empty_paths = "keep")]
+const PL_LIB_PATH: Vec<PathBuf>;
+
+#[envflag(parser = custom_parser)]
+const PL_CUSTOM: Option<Vec<PathBuf>>;
+
+fn custom_parser(_key: &str, val: &str) -> anyhow::Result<Vec<PathBuf>> {
+    Ok(val.split(',').map(PathBuf::from).collect())
+}
+
+fn layer() -> DotEnv {
+    DotEnv::parse(
+        "test.env",
+        "PL_SEARCH_PATH=/bin::/usr/bin:\nPL_PLUGIN_PATH=/a::/b\nPL_LIB_PATH=/a::/b\nPL_CUSTOM=/a,/b",
+    )
+    .unwrap()
+}
+
+fn paths(x: &[&str]) -> Vec<PathBuf> {
+    x.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn path_list_drops_empty() {
+    assert_eq!(
+        PL_SEARCH_PATH.fetch_from(&layer()).unwrap(),
+        paths(&["/bin", "/usr/bin"])
+    );
+}
+
+#[test]
+fn path_list_empty_error() {
+    assert_eq!(
+        PLUGIN_PATH.fetch_from(&layer()).unwrap_err().to_string(),
+        "Empty path at 1 of ENV \"PL_PLUGIN_PATH\""
+    );
+}
+
+#[test]
+fn path_list_empty_keep() {
+    assert_eq!(
+        PL_LIB_PATH.fetch_from(&layer()).unwrap(),
+        paths(&["/a", "", "/b"])
+    );
+}
+
+#[test]
+fn path_list_custom_parser() {
+    assert_eq!(
+        PL_CUSTOM.fetch_from(&layer()).unwrap(),
+        Some(paths(&["/a", "/b"]))
+    );
+}
+
+#[test]
+fn path_list_absent() {
+    assert!(PL_SEARCH_PATH
+        .fetch_from(&DotEnv::parse("test.env", "").unwrap())
+        .unwrap()
+        .is_empty());
+    assert!(declared::PL_DECLARED.fetch().unwrap().is_empty());
+}
+
+#[test]
+fn path_list_meta() {
+    assert_eq!(PL_SEARCH_PATH.meta().type_name, "Vec<PathBuf>");
+}
+
+#[test]
+fn path_list_tilde() {
+    #[envflag]
+    const PL_TILDE: Vec<PathBuf>;
+
+    std::env::set_var("HOME", "/home/me");
+    std::env::set_var("PL_TILDE", "~/bin:~:~other/bin:/opt/~");
+    assert_eq!(
+        PL_TILDE.fetch_with_source().unwrap(),
+        Some((
+            paths(&["/home/me/bin", "/home/me", "~other/bin", "/opt/~"]),
+            Source::Env {
+                key: "PL_TILDE".to_string()
+            }
+        ))
+    );
+}
+
+#[test]
+fn path_list_not_unicode() {
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    #[envflag]
+    const PL_RAW: Vec<PathBuf>;
+
+    let raw = std::ffi::OsString::from_vec(b"/a\xff:/b".to_vec());
+    std::env::set_var("PL_RAW", &raw);
+    let res = PL_RAW.fetch().unwrap();
+    assert_eq!(res[0].as_os_str().as_bytes(), b"/a\xff");
+    assert_eq!(res[1], PathBuf::from("/b"));
+    assert!(PL_RAW.diagnose().is_ok());
+}
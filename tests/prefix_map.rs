@@ -0,0 +1,115 @@
+use rs_envflag::strict::unknown_vars;
+use rs_envflag::Flag;
+use rs_envflag_macros::*;
+use std::collections::BTreeMap;
+
+/// The declared type is what `fetch()` returns, so importing it is not an unused import.
+mod declared {
+    #![deny(unused_imports)]
+
+    use rs_envflag_macros::*;
+    use std::collections::BTreeMap;
+
+    #[envflag(prefix = "PM_DECLARED_")]
+    pub const LIMITS: BTreeMap<String, i64>;
+}
+
+#[test]
+fn prefix_map_empty() {
+    #[envflag(prefix = "PM_EMPTY_")]
+    const FEATURES: BTreeMap<String, bool>;
+
+    assert!(FEATURES.fetch().unwrap().is_empty());
+    assert!(declared::LIMITS.fetch().unwrap().is_empty());
+}
+
+#[test]
+fn prefix_map_fetch() {
+    #[envflag(prefix = "PM_FETCH_")]
+    const FEATURES: BTreeMap<String, bool>;
+
+    std::env::set_var("PM_FETCH_SEARCH", "true");
+    std::env::set_var("PM_FETCH_CHAT", "false");
+    assert_eq!(
+        FEATURES.fetch().unwrap(),
+        BTreeMap::from([("CHAT".to_string(), false), ("SEARCH".to_string(), true)])
+    );
+}
+
+#[test]
+fn prefix_map_excludes_prefix() {
+    #[envflag(prefix = "PM_EXACT_")]
+    const FEATURES: BTreeMap<String, bool>;
+
+    std::env::set_var("PM_EXACT_", "true");
+    assert!(FEATURES.fetch().unwrap().is_empty());
+}
+
+#[test]
+fn prefix_map_names_as_they_are() {
+    #[envflag(prefix = "PM_ATTR_")]
+    const ATTRS: BTreeMap<String, String>;
+
+    std::env::set_var("PM_ATTR_service.name", "api");
+    assert_eq!(
+        ATTRS.fetch().unwrap(),
+        BTreeMap::from([("service.name".to_string(), "api".to_string())])
+    );
+}
+
+#[test]
+fn prefix_map_parser() {
+    #[envflag(prefix = "PM_LIMIT_", parser = limits_parser)]
+    const LIMITS: BTreeMap<String, Vec<i64>>;
+
+    fn limits_parser(key: &str, val: &str) -> anyhow::Result<Vec<i64>> {
+        val.split(',')
+            .map(|x| rs_envflag::i64_parser(key, x))
+            .collect()
+    }
+
+    std::env::set_var("PM_LIMIT_A", "1,2");
+    assert_eq!(LIMITS.fetch().unwrap()["A"], [1, 2]);
+}
+
+#[test]
+fn prefix_map_meta() {
+    /// feature switches
+    #[envflag(prefix = "PM_META_")]
+    const FEATURES: BTreeMap<String, bool>;
+
+    assert_eq!(FEATURES.meta().type_name, "BTreeMap<String, bool>");
+    assert_eq!(FEATURES.meta().doc, " feature switches");
+}
+
+#[test]
+fn prefix_map_errors() {
+    #[envflag(prefix = "PM_ERROR_")]
+    const FEATURES: BTreeMap<String, bool>;
+
+    std::env::set_var("PM_ERROR_SEARCH", "true");
+    std::env::set_var("PM_ERROR_VOICE", "maybe");
+    std::env::set_var("PM_ERROR_VIDEO", "sure");
+    let entries = FEATURES.fetch_entries();
+    assert_eq!(entries.len(), 3);
+    assert!(*entries["SEARCH"].as_ref().unwrap());
+    assert!(entries["VOICE"].is_err());
+    let err = FEATURES.fetch().unwrap_err().to_string();
+    assert!(err.contains("PM_ERROR_VIDEO") && err.contains("PM_ERROR_VOICE"));
+    assert!(!FEATURES.diagnose().is_ok());
+}
+
+#[test]
+fn prefix_map_unknown() {
+    #[envflag(prefix = "PM_UNKNOWN_")]
+    const FEATURES: BTreeMap<String, bool>;
+
+    std::env::set_var("PM_UNKNOWN_SEARCH", "true");
+    std::env::set_var("PM_UNKNOWN_", "true");
+    let flags: &[&dyn Flag] = &[&FEATURES];
+    let unknown: Vec<String> = unknown_vars(flags, &["PM_UNKNOWN"])
+        .into_iter()
+        .map(|x| x.key)
+        .collect();
+    assert_eq!(unknown, ["PM_UNKNOWN_"]);
+}
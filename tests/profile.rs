@@ -0,0 +1,67 @@
+use rs_envflag_macros::*;
+
+#[envflag(default = 1, default(dev) = 2, default(prod) = 3)]
+const PROFILE_I64: i64;
+
+#[envflag(default = "local", default(prod) = "remote")]
+const PROFILE_STR: String;
+
+#[test]
+fn profiles() {
+    rs_envflag::set_profile_key("PROFILE_UNDER_TEST");
+    assert_eq!(rs_envflag::active_profile().unwrap(), None);
+    assert_eq!(PROFILE_I64.fetch().unwrap(), 1);
+    assert_eq!(PROFILE_STR.fetch().unwrap(), "local");
+
+    std::env::set_var("PROFILE_UNDER_TEST", "dev");
+    assert_eq!(
+        rs_envflag::active_profile().unwrap(),
+        Some("dev".to_string())
+    );
+    assert_eq!(PROFILE_I64.fetch().unwrap(), 2);
+    assert_eq!(PROFILE_STR.fetch().unwrap(), "local");
+
+    std::env::set_var("PROFILE_UNDER_TEST", "prod");
+    assert_eq!(PROFILE_I64.fetch().unwrap(), 3);
+    assert_eq!(PROFILE_STR.fetch().unwrap(), "remote");
+    assert_eq!(
+        PROFILE_I64.fetch_with_source().unwrap(),
+        (
+            3,
+            rs_envflag::Source::Default {
+                profile: Some("prod".to_string())
+            }
+        )
+    );
+
+    std::env::set_var("PROFILE_I64", "42");
+    assert_eq!(PROFILE_I64.fetch().unwrap(), 42);
+}
+
+#[test]
+fn profile_from_layer() {
+    use rs_envflag::{active_profile_from, Args, DotEnv, Source};
+
+    rs_envflag::set_profile_key("PROFILE_UNDER_TEST");
+    let args = Args::new(["--profile-under-test=prod"]);
+    assert_eq!(
+        active_profile_from(&args).unwrap(),
+        Some("prod".to_string())
+    );
+    assert_eq!(
+        PROFILE_STR.fetch_from_with_source(&args).unwrap(),
+        (
+            "remote".to_string(),
+            Source::Default {
+                profile: Some("prod".to_string())
+            }
+        )
+    );
+
+    let dotenv = DotEnv::parse("test.env", "PROFILE_UNDER_TEST=dev\n").unwrap();
+    assert_eq!(PROFILE_I64.fetch_from(&dotenv).unwrap(), 2);
+
+    let empty = DotEnv::parse("test.env", "PROFILE_UNDER_TEST=\n").unwrap();
+    assert_eq!(active_profile_from(&empty).unwrap(), None);
+    assert_eq!(PROFILE_I64.fetch_from(&empty).unwrap(), 1);
+}
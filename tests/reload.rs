@@ -0,0 +1,193 @@
+use rs_envflag::reload::Reloadable;
+use rs_envflag::{DotEnv, Flag, Resolver, Source};
+use rs_envflag_macros::*;
+use std::time::Duration;
+
+mod common;
+use common::{temp_file, Global};
+
+#[envflag(default = 16)]
+const RELOAD_POOL_SIZE: i64;
+
+#[envflag(default = false)]
+const RELOAD_VERBOSE: bool;
+
+const FLAGS: &[&dyn Flag] = &[&RELOAD_POOL_SIZE, &RELOAD_VERBOSE];
+
+#[test]
+fn reload() {
+    let path = temp_file("reload.env", "RELOAD_POOL_SIZE=32\n");
+    let store = Reloadable::open(&path, FLAGS).unwrap();
+    assert_eq!(RELOAD_POOL_SIZE.fetch_from(&store).unwrap(), 32);
+    assert_eq!(
+        RELOAD_POOL_SIZE.fetch_from_with_source(&store).unwrap().1,
+        Source::File {
+            path: path.clone(),
+            key: "RELOAD_POOL_SIZE".to_string(),
+        }
+    );
+
+    std::fs::write(&path, "RELOAD_POOL_SIZE=64\nRELOAD_VERBOSE=true\n").unwrap();
+    store.reload().unwrap();
+    assert_eq!(RELOAD_POOL_SIZE.fetch_from(&store).unwrap(), 64);
+    assert!(RELOAD_VERBOSE.fetch_from(&store).unwrap());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn reload_invalid() {
+    let path = temp_file(
+        "reload_invalid.env",
+        "RELOAD_POOL_SIZE=64\nRELOAD_VERBOSE=true\n",
+    );
+    let store = Reloadable::open(&path, FLAGS).unwrap();
+
+    std::fs::write(&path, "RELOAD_POOL_SIZE=128\nRELOAD_VERBOSE=maybe\n").unwrap();
+    assert!(store.reload().is_err());
+    assert_eq!(RELOAD_POOL_SIZE.fetch_from(&store).unwrap(), 64);
+    assert!(RELOAD_VERBOSE.fetch_from(&store).unwrap());
+
+    std::fs::write(&path, "RELOAD_POOL_SIZE=\"unterminated\n").unwrap();
+    assert!(store.reload().is_err());
+    assert_eq!(RELOAD_POOL_SIZE.fetch_from(&store).unwrap(), 64);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn reload_if_changed_same_length() {
+    let path = temp_file("same_length.env", "RELOAD_POOL_SIZE=11\n");
+    let store = Reloadable::open(&path, FLAGS).unwrap();
+    assert!(!store.reload_if_changed().unwrap());
+
+    std::fs::write(&path, "RELOAD_POOL_SIZE=22\n").unwrap();
+    assert!(store.reload_if_changed().unwrap());
+    assert_eq!(RELOAD_POOL_SIZE.fetch_from(&store).unwrap(), 22);
+    assert!(!store.reload_if_changed().unwrap());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn open_invalid() {
+    let path = temp_file("open_invalid.env", "RELOAD_POOL_SIZE=many\n");
+    assert!(Reloadable::open(&path, FLAGS).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "watch")]
+#[test]
+fn watch() {
+    let path = temp_file("watch.env", "RELOAD_POOL_SIZE=1\n");
+    let store = Reloadable::open(&path, FLAGS).unwrap();
+    store.watch().unwrap();
+
+    std::fs::write(&path, "RELOAD_POOL_SIZE=1000\n").unwrap();
+    assert_eq!(wait_for_change(&store, 1), 1000);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "watch")]
+#[test]
+fn watch_renamed() {
+    let path = temp_file("watch_renamed.env", "RELOAD_POOL_SIZE=1\n");
+    let store = Reloadable::open(&path, FLAGS).unwrap();
+    store.watch().unwrap();
+
+    // Editors often write a new file and rename it over the old one.
+    let new = temp_file("watch_renamed.env.new", "RELOAD_POOL_SIZE=2\n");
+    std::fs::rename(&new, &path).unwrap();
+    assert_eq!(wait_for_change(&store, 1), 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn poll() {
+    let path = temp_file("poll.env", "RELOAD_POOL_SIZE=1\n");
+    let store = Reloadable::open(&path, FLAGS).unwrap();
+    store.poll(Duration::from_millis(10));
+
+    std::fs::write(&path, "RELOAD_POOL_SIZE=1000\n").unwrap();
+    assert_eq!(wait_for_change(&store, 1), 1000);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(all(unix, feature = "signal"))]
+#[test]
+fn reload_on_sighup() {
+    let path = temp_file("sighup.env", "RELOAD_POOL_SIZE=1\n");
+    let store = Reloadable::open(&path, FLAGS).unwrap();
+    store.reload_on_sighup().unwrap();
+
+    std::fs::write(&path, "RELOAD_POOL_SIZE=2\n").unwrap();
+    signal_hook::low_level::raise(signal_hook::consts::SIGHUP).unwrap();
+    assert_eq!(wait_for_change(&store, 1), 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn reload_overridden_by_other_layers() {
+    #[envflag(default = 1)]
+    const RELOAD_REPLICAS: i64;
+
+    const FLAGS: &[&dyn Flag] = &[&RELOAD_REPLICAS];
+
+    let _global = Global::lock();
+    let path = temp_file("overridden.env", "RELOAD_REPLICAS=2\n");
+    let store = Reloadable::open(&path, FLAGS).unwrap();
+    let higher = DotEnv::parse("higher.env", "RELOAD_REPLICAS=3").unwrap();
+    Resolver::new().layer(higher).layer(store.clone()).install();
+
+    std::fs::write(&path, "RELOAD_REPLICAS=many\n").unwrap();
+    let res = store.reload();
+    Resolver::uninstall();
+    res.unwrap();
+    assert!(RELOAD_REPLICAS.fetch_from(&*store).is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn reload_interpolates_other_layers() {
+    #[envflag(interpolate, default = 1)]
+    const RELOAD_PORT: i64;
+
+    const FLAGS: &[&dyn Flag] = &[&RELOAD_PORT];
+
+    let _global = Global::lock();
+    let path = temp_file("interpolated.env", "RELOAD_PORT=80\n");
+    let store = Reloadable::open(&path, FLAGS).unwrap();
+    let higher = DotEnv::parse("higher.env", "RELOAD_BASE_PORT=8080").unwrap();
+    Resolver::new().layer(higher).layer(store.clone()).install();
+
+    std::fs::write(&path, "RELOAD_PORT=${RELOAD_BASE_PORT}\n").unwrap();
+    let res = store.reload();
+    let port = RELOAD_PORT.fetch();
+    Resolver::uninstall();
+    res.unwrap();
+    assert_eq!(port.unwrap(), 8080);
+
+    // Not installed, the file is validated alone.
+    std::fs::write(&path, "RELOAD_PORT=${RELOAD_BASE_PORT}\n").unwrap();
+    assert!(store.reload().is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Polls the store for up to 2 seconds until the pool size is no longer `old`.
+fn wait_for_change(store: &Reloadable, old: i64) -> i64 {
+    let mut value = old;
+    for _ in 0..200 {
+        value = RELOAD_POOL_SIZE.fetch_from(store).unwrap();
+        if value != old {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    value
+}
@@ -0,0 +1,59 @@
+use rs_envflag::{rollout_parser, Rollout};
+use rs_envflag_macros::*;
+
+#[envflag(default_str = "0%")]
+const ROLLOUT_NEW_PATH: Rollout;
+
+#[envflag(default = &Rollout::ON)]
+const ROLLOUT_ON: Rollout;
+
+#[test]
+fn parse() {
+    let percent = |x: &str| rollout_parser("K", x).unwrap().percent();
+    assert_eq!(percent("25%"), 25.0);
+    assert_eq!(percent("12.5%"), 12.5);
+    assert_eq!(percent("7"), 7.0);
+    assert_eq!(percent("100 %"), 100.0);
+    assert_eq!(percent("true"), 100.0);
+    assert_eq!(percent("false"), 0.0);
+    assert!(rollout_parser("K", "101%").is_err());
+    assert!(rollout_parser("K", "-1%").is_err());
+    assert!(rollout_parser("K", "half").is_err());
+    assert_eq!(Rollout::from_percent(12.5).unwrap().to_string(), "12.5%");
+}
+
+#[test]
+fn stable() {
+    let ids: Vec<u64> = (0..10000).collect();
+    let enabled = |percent: f64| -> Vec<u64> {
+        let rollout = Rollout::from_percent(percent).unwrap();
+        ids.iter()
+            .copied()
+            .filter(|id| rollout.is_enabled_for("NEW_PATH", id))
+            .collect()
+    };
+    let quarter = enabled(25.0);
+    assert!((2200..2800).contains(&quarter.len()));
+    assert_eq!(quarter, enabled(25.0));
+    // Increasing the percentage only adds ids.
+    let half = enabled(50.0);
+    assert!(quarter.iter().all(|id| half.contains(id)));
+    assert!(enabled(0.0).is_empty());
+    assert_eq!(enabled(100.0).len(), ids.len());
+
+    // Pinned, so that hashing never changes silently.
+    let half = Rollout::from_percent(50.0).unwrap();
+    assert!(!half.is_enabled_for("NEW_PATH", "tenant-1"));
+    assert!(half.is_enabled_for("NEW_PATH", "tenant-2"));
+    assert!(half.is_enabled_for("NEW_PATH", "tenant-3"));
+}
+
+#[test]
+fn flags() {
+    assert!(!ROLLOUT_NEW_PATH.is_enabled_for("tenant-1").unwrap());
+    assert!(ROLLOUT_ON.is_enabled_for(&42).unwrap());
+    std::env::set_var("ROLLOUT_NEW_PATH", "100%");
+    assert!(ROLLOUT_NEW_PATH.is_enabled_for("tenant-1").unwrap());
+    std::env::set_var("ROLLOUT_NEW_PATH", "150%");
+    assert!(ROLLOUT_NEW_PATH.is_enabled_for("tenant-1").is_err());
+}
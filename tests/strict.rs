@@ -0,0 +1,108 @@
+use rs_envflag::strict::{check_unknown, unknown_vars, Policy, Unknown};
+use rs_envflag::Flag;
+use rs_envflag_macros::*;
+
+#[envflag(default = 30)]
+const STRICT_TIMEOUT: i64;
+
+#[envflag]
+const STRICT_HOST: Option<String>;
+
+const FLAGS: &[&dyn Flag] = &[&STRICT_TIMEOUT, &STRICT_HOST];
+
+fn set_vars() {
+    std::env::set_var("STRICT_TIMEOUT", "60");
+    std::env::set_var("STRICT_TIMOUT", "60");
+    std::env::set_var("STRICT_SOMETHING_ELSE", "1");
+    std::env::set_var("UNSTRICT_TIMOUT", "1");
+}
+
+#[test]
+fn unknown() {
+    set_vars();
+    assert_eq!(
+        unknown_vars(FLAGS, &["STRICT_"]),
+        [
+            Unknown {
+                key: "STRICT_SOMETHING_ELSE".to_string(),
+                suggestion: None,
+            },
+            Unknown {
+                key: "STRICT_TIMOUT".to_string(),
+                suggestion: Some("STRICT_TIMEOUT".to_string()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn check_unknown_warn() {
+    set_vars();
+    assert_eq!(
+        check_unknown(FLAGS, &["STRICT_"], Policy::Warn)
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn check_unknown_error() {
+    set_vars();
+    let err = check_unknown(FLAGS, &["STRICT_"], Policy::Error).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unknown ENV STRICT_SOMETHING_ELSE. Unknown ENV STRICT_TIMOUT. Did you mean STRICT_TIMEOUT?"
+    );
+}
+
+#[test]
+fn check_unknown_other_prefix() {
+    set_vars();
+    assert!(check_unknown(FLAGS, &["NO_SUCH_PREFIX_"], Policy::Error)
+        .unwrap()
+        .is_empty());
+}
+
+/// the token
+#[envflag(env_name = ["SUGGEST_TOKEN", "SUGGEST_LEGACY_TOKEN"], allow_file)]
+const SUGGEST_TOKEN: Option<String>;
+
+#[envflag(env_name = "SUGGEST_SHARD_{}_WEIGHT", default = 1)]
+const SUGGEST_SHARD_WEIGHT: i64;
+
+#[envflag(prefix = "SUGGEST_FEATURE_")]
+const SUGGEST_FEATURES: std::collections::BTreeMap<String, bool>;
+
+#[envflag(indexed)]
+const SUGGEST_DATABASES: Vec<String>;
+
+#[test]
+fn suggest_other_names() {
+    let flags: &[&dyn Flag] = &[
+        &SUGGEST_TOKEN,
+        &SUGGEST_SHARD_WEIGHT,
+        &SUGGEST_FEATURES,
+        &SUGGEST_DATABASES,
+    ];
+    let vars = [
+        ("SUGGEST_LEGACY_TOKN", "SUGGEST_LEGACY_TOKEN"),
+        ("SUGGEST_TOKEN_FIEL", "SUGGEST_TOKEN_FILE"),
+        ("SUGGEST_SHARD_7_WIEGHT", "SUGGEST_SHARD_7_WEIGHT"),
+        ("SUGGEST_FEATRUE_DARK", "SUGGEST_FEATURE_DARK"),
+        ("SUGGEST_DATABASE_0", "SUGGEST_DATABASES_0"),
+    ];
+    for (key, _) in vars {
+        std::env::set_var(key, "1");
+    }
+    std::env::set_var("SUGGEST_SHARD_1_WEIGHT", "2");
+    let mut expected: Vec<Unknown> = vars
+        .iter()
+        .map(|(key, suggestion)| Unknown {
+            key: key.to_string(),
+            suggestion: Some(suggestion.to_string()),
+        })
+        .collect();
+    expected.sort_by(|a, b| a.key.cmp(&b.key));
+    assert_eq!(unknown_vars(flags, &["SUGGEST_"]), expected);
+}
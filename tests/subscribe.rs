@@ -0,0 +1,169 @@
+use rs_envflag::{notify, Resolver};
+use rs_envflag_macros::*;
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::{dotenv, Global};
+
+#[test]
+fn subscribe_with_default() {
+    #[envflag(default = 4)]
+    const SUB_POOL_SIZE: i64;
+
+    let _global = Global::lock();
+    let changes = Arc::new(Mutex::new(vec![]));
+    let _sub = {
+        let changes = changes.clone();
+        SUB_POOL_SIZE.subscribe(move |old, new| changes.lock().unwrap().push((*old, *new)))
+    };
+    Resolver::new().layer(dotenv("SUB_POOL_SIZE=8")).install();
+    Resolver::new().layer(dotenv("SUB_POOL_SIZE=16")).install();
+    Resolver::uninstall();
+    assert_eq!(*changes.lock().unwrap(), vec![(4, 8), (8, 16), (16, 4)]);
+}
+
+#[test]
+fn subscribe_optional() {
+    #[envflag]
+    const SUB_NAME: Option<String>;
+
+    let _global = Global::lock();
+    let names = Arc::new(Mutex::new(vec![]));
+    let _sub = {
+        let names = names.clone();
+        SUB_NAME.subscribe(move |old, new| names.lock().unwrap().push((old.clone(), new.clone())))
+    };
+    Resolver::new().layer(dotenv("SUB_NAME=a")).install();
+    Resolver::uninstall();
+    assert_eq!(
+        *names.lock().unwrap(),
+        vec![(None, Some("a".to_string())), (Some("a".to_string()), None)]
+    );
+}
+
+#[test]
+fn subscribe_unchanged() {
+    #[envflag(default = 4)]
+    const SUB_UNCHANGED: i64;
+
+    let _global = Global::lock();
+    let changes = Arc::new(Mutex::new(vec![]));
+    let _sub = {
+        let changes = changes.clone();
+        SUB_UNCHANGED.subscribe(move |old, new| changes.lock().unwrap().push((*old, *new)))
+    };
+    Resolver::new().layer(dotenv("SUB_UNCHANGED=4")).install();
+    Resolver::uninstall();
+    assert!(changes.lock().unwrap().is_empty());
+}
+
+#[test]
+fn subscribe_skips_invalid() {
+    #[envflag(default = 4)]
+    const SUB_INVALID: i64;
+
+    let _global = Global::lock();
+    let changes = Arc::new(Mutex::new(vec![]));
+    let _sub = {
+        let changes = changes.clone();
+        SUB_INVALID.subscribe(move |old, new| changes.lock().unwrap().push((*old, *new)))
+    };
+    Resolver::new().layer(dotenv("SUB_INVALID=many")).install();
+    Resolver::new().layer(dotenv("SUB_INVALID=8")).install();
+    Resolver::uninstall();
+    assert_eq!(*changes.lock().unwrap(), vec![(4, 8), (8, 4)]);
+}
+
+#[test]
+fn subscribe_dropped() {
+    #[envflag(default = 4)]
+    const SUB_DROPPED: i64;
+
+    let mut global = Global::lock();
+    let changes = Arc::new(Mutex::new(vec![]));
+    let sub = {
+        let changes = changes.clone();
+        SUB_DROPPED.subscribe(move |old, new| changes.lock().unwrap().push((*old, *new)))
+    };
+    drop(sub);
+    global.set_var("SUB_DROPPED", "32");
+    notify();
+    assert!(changes.lock().unwrap().is_empty());
+}
+
+#[test]
+fn subscribe_detached() {
+    #[envflag(default = 4)]
+    const SUB_DETACHED: i64;
+
+    let mut global = Global::lock();
+    let changes = Arc::new(Mutex::new(vec![]));
+    {
+        let changes = changes.clone();
+        SUB_DETACHED
+            .subscribe(move |old, new| changes.lock().unwrap().push((*old, *new)))
+            .detach();
+    }
+    global.set_var("SUB_DETACHED", "32");
+    notify();
+    assert_eq!(*changes.lock().unwrap(), vec![(4, 32)]);
+}
+
+#[test]
+fn subscribe_reentrant() {
+    #[envflag(default = 4)]
+    const SUB_LEADER: i64;
+
+    #[envflag(default = 4)]
+    const SUB_FOLLOWER: i64;
+
+    let changes = Arc::new(Mutex::new(vec![]));
+    let _leader = SUB_LEADER.subscribe(|_, new| SUB_FOLLOWER.set_override(*new).unwrap());
+    let _follower = {
+        let changes = changes.clone();
+        SUB_FOLLOWER.subscribe(move |old, new| changes.lock().unwrap().push((*old, *new)))
+    };
+    SUB_LEADER.set_override(8).unwrap();
+    assert_eq!(SUB_FOLLOWER.fetch().unwrap(), 8);
+    assert_eq!(*changes.lock().unwrap(), vec![(4, 8)]);
+}
+
+#[test]
+fn subscribe_reentrant_across_threads() {
+    #[envflag(default = 4)]
+    const SUB_PING: i64;
+
+    #[envflag(default = 4)]
+    const SUB_PONG: i64;
+
+    // Callbacks are slow, and the second thread starts while the first one is in a callback,
+    // so both threads are in callbacks at the same time if each calls its own.
+    // Both flags converge to the larger value.
+    let _ping = SUB_PING.subscribe(|_, new| {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        SUB_PONG
+            .set_override(*new.max(&SUB_PONG.fetch().unwrap()))
+            .unwrap();
+    });
+    let _pong = SUB_PONG.subscribe(|_, new| {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        SUB_PING
+            .set_override(*new.max(&SUB_PING.fetch().unwrap()))
+            .unwrap();
+    });
+    let (tx, rx) = std::sync::mpsc::channel();
+    for (flag, value, delay) in [(&SUB_PONG, 16, 0), (&SUB_PING, 8, 20)] {
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(delay));
+            flag.set_override(value).unwrap();
+            tx.send(()).unwrap();
+        });
+    }
+    for _ in 0..2 {
+        rx.recv_timeout(std::time::Duration::from_secs(10))
+            .expect("deadlock");
+    }
+    assert_eq!(SUB_PING.fetch().unwrap(), 16);
+    assert_eq!(SUB_PONG.fetch().unwrap(), 16);
+}
@@ -0,0 +1,134 @@
+use rs_envflag::strict::unknown_vars;
+use rs_envflag::{DotEnv, Flag, Source};
+use rs_envflag_macros::*;
+
+#[envflag(env_name = "TPL_SHARD_{}_HOST", default = "localhost")]
+const SHARD_HOST: String;
+
+#[envflag(env_name = "TPL_SHARD_{}_WEIGHT")]
+const SHARD_WEIGHT: Option<i64>;
+
+fn layer() -> DotEnv {
+    DotEnv::parse("test.env", "TPL_SHARD_3_HOST=db3\nTPL_SHARD_1_WEIGHT=x").unwrap()
+}
+
+#[test]
+fn key_for() {
+    assert_eq!(SHARD_HOST.template(), "TPL_SHARD_{}_HOST");
+    assert_eq!(SHARD_HOST.key_for(3), "TPL_SHARD_3_HOST");
+}
+
+#[test]
+fn fetch_for() {
+    assert_eq!(
+        SHARD_HOST.fetch_for_from_with_source(3, &layer()).unwrap(),
+        (
+            "db3".to_string(),
+            Source::File {
+                path: "test.env".into(),
+                key: "TPL_SHARD_3_HOST".to_string(),
+            }
+        )
+    );
+    assert_eq!(SHARD_WEIGHT.fetch_for_from(3, &layer()).unwrap(), None);
+}
+
+#[test]
+fn fetch_for_default() {
+    assert_eq!(SHARD_HOST.fetch_for_from(4, &layer()).unwrap(), "localhost");
+}
+
+#[test]
+fn fetch_for_error() {
+    let err = SHARD_WEIGHT.fetch_for_from(1, &layer()).unwrap_err();
+    assert_eq!(err.to_string(), "Fail to fetch ENV TPL_SHARD_1_WEIGHT");
+    let msg = format!("{:#}", err);
+    assert!(
+        msg.contains("Unrecognized ENV \"TPL_SHARD_1_WEIGHT\""),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+fn indices() {
+    assert!(SHARD_WEIGHT.indices().is_empty());
+    for i in [10, 2, 1] {
+        std::env::set_var(format!("TPL_SHARD_{}_WEIGHT", i), i.to_string());
+    }
+    std::env::set_var("TPL_SHARD__WEIGHT", "0");
+    std::env::set_var("TPL_SHARD_a_WEIGHT", "0");
+    assert_eq!(SHARD_WEIGHT.indices(), ["1", "2", "a", "10"]);
+    assert_eq!(SHARD_WEIGHT.fetch_for(10).unwrap(), Some(10));
+    let total: i64 = SHARD_WEIGHT
+        .indices()
+        .iter()
+        .filter_map(|i| SHARD_WEIGHT.fetch_for(i).unwrap())
+        .sum();
+    assert_eq!(total, 13);
+}
+
+#[test]
+fn keys() {
+    #[envflag(env_name = "TPL_KEYS_{}_PORT", allow_file)]
+    const PORT: Option<i64>;
+
+    std::env::set_var("TPL_KEYS_1_PORT", "1");
+    std::env::set_var("TPL_KEYS_2_PORT_FILE", "/nonexistent");
+    assert_eq!(PORT.key(), "TPL_KEYS_{}_PORT");
+    assert_eq!(
+        PORT.keys(),
+        [
+            "TPL_KEYS_1_PORT",
+            "TPL_KEYS_1_PORT_FILE",
+            "TPL_KEYS_2_PORT",
+            "TPL_KEYS_2_PORT_FILE"
+        ]
+    );
+}
+
+#[test]
+fn indices_of_files() {
+    #[envflag(env_name = "TPL_FILES_{}", allow_file)]
+    const SH: Option<String>;
+
+    std::env::set_var("TPL_FILES_3_FILE", "/nonexistent");
+    assert_eq!(SH.indices(), ["3"]);
+    assert_eq!(SH.keys(), ["TPL_FILES_3", "TPL_FILES_3_FILE"]);
+}
+
+#[test]
+fn unknown() {
+    #[envflag(env_name = "TPL_UNKNOWN_{}_HOST")]
+    const HOST: Option<String>;
+
+    std::env::set_var("TPL_UNKNOWN_3_HOST", "db3");
+    std::env::set_var("TPL_UNKNOWN_3_HOTS", "db3");
+    let unknown: Vec<String> = unknown_vars(&[&HOST], &["TPL_UNKNOWN_"])
+        .into_iter()
+        .map(|x| x.key)
+        .collect();
+    assert_eq!(unknown, ["TPL_UNKNOWN_3_HOTS"]);
+}
+
+#[test]
+fn diagnose() {
+    #[envflag(env_name = "TPL_DIAGNOSE_{}_WEIGHT", default = 1)]
+    const WEIGHT: i64;
+
+    assert_eq!(WEIGHT.diagnose().value, None);
+    std::env::set_var("TPL_DIAGNOSE_1_WEIGHT", "2");
+    std::env::set_var("TPL_DIAGNOSE_2_WEIGHT", "3");
+    let diagnosis = WEIGHT.diagnose();
+    assert_eq!(
+        diagnosis.value.as_deref(),
+        Some(r#"{"TPL_DIAGNOSE_1_WEIGHT": 2, "TPL_DIAGNOSE_2_WEIGHT": 3}"#)
+    );
+    assert_eq!(
+        diagnosis.source.unwrap().to_string(),
+        "env TPL_DIAGNOSE_1_WEIGHT"
+    );
+
+    std::env::set_var("TPL_DIAGNOSE_3_WEIGHT", "x");
+    assert!(!WEIGHT.diagnose().is_ok());
+}
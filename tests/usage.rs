@@ -53,6 +53,21 @@ fn env_rename() {
     assert_eq!(ABC.fetch().unwrap(), "haha");
 }
 
+#[test]
+fn without_macros() {
+    use rs_envflag::{EnvFlag, EnvFlagWithDefault};
+
+    const WITHOUT_MACROS: EnvFlagWithDefault<i64, i64> = {
+        let mut env = EnvFlag::new("WITHOUT_MACROS", rs_envflag::i64_parser);
+        env.trim = true;
+        EnvFlagWithDefault::new(env, &42)
+    };
+
+    assert_eq!(WITHOUT_MACROS.fetch().unwrap(), 42);
+    std::env::set_var("WITHOUT_MACROS", " 7 ");
+    assert_eq!(WITHOUT_MACROS.fetch().unwrap(), 7);
+}
+
 #[test]
 fn i64flag_unset() {
     #[envflag]
@@ -206,6 +221,181 @@ fn customized_parser_wd_set() {
     assert_eq!(CSTM_FLAG_WD_SET.fetch().unwrap(), V("xixi".to_string()));
 }
 
+#[test]
+fn default_str_unset() {
+    #[envflag(parser=secs_parser, default_str="30s")]
+    const DEFAULT_STR_UNSET: std::time::Duration;
+
+    assert_eq!(
+        DEFAULT_STR_UNSET.fetch().unwrap(),
+        std::time::Duration::from_secs(30)
+    );
+}
+
+#[test]
+fn default_str_set() {
+    #[envflag(parser=secs_parser, default_str="30s")]
+    const DEFAULT_STR_SET: std::time::Duration;
+
+    std::env::set_var("DEFAULT_STR_SET", "5s");
+    assert_eq!(
+        DEFAULT_STR_SET.fetch().unwrap(),
+        std::time::Duration::from_secs(5)
+    );
+}
+
+#[test]
+fn default_str_invalid() {
+    #[envflag(parser=secs_parser, default_str="30")]
+    const DEFAULT_STR_INVALID: std::time::Duration;
+
+    assert!(DEFAULT_STR_INVALID.validate().is_err());
+    assert!(DEFAULT_STR_INVALID.fetch().is_err());
+}
+
+#[test]
+fn default_str_builtin() {
+    #[envflag(default_str = "42")]
+    const DEFAULT_STR_BUILTIN: i64;
+
+    assert_eq!(DEFAULT_STR_BUILTIN.fetch().unwrap(), 42);
+}
+
+#[test]
+fn default_fn_unset() {
+    #[envflag(default_fn = answer)]
+    const DEFAULT_FN_UNSET: i64;
+
+    assert_eq!(DEFAULT_FN_UNSET.fetch().unwrap(), 42);
+}
+
+#[test]
+fn default_fn_set() {
+    #[envflag(default_fn = answer)]
+    const DEFAULT_FN_SET: i64;
+
+    std::env::set_var("DEFAULT_FN_SET", "2333");
+    assert_eq!(DEFAULT_FN_SET.fetch().unwrap(), 2333);
+}
+
+#[test]
+fn default_fn_wo_clone() {
+    #[envflag(parser=w_parser, default_fn=W::new)]
+    const DEFAULT_FN_WO_CLONE: W;
+
+    assert_eq!(DEFAULT_FN_WO_CLONE.fetch().unwrap().0, "xixi");
+}
+
+#[test]
+fn depends_on_unset() {
+    #[envflag(default = 8080)]
+    const DEP_PORT_UNSET: i64;
+
+    #[envflag(default_fn=metrics_port, depends_on=[DEP_PORT_UNSET])]
+    const DEP_METRICS_PORT_UNSET: i64;
+
+    fn metrics_port() -> anyhow::Result<i64> {
+        Ok(DEP_PORT_UNSET.fetch()? + 1)
+    }
+
+    assert_eq!(DEP_METRICS_PORT_UNSET.fetch().unwrap(), 8081);
+    assert_eq!(
+        DEP_METRICS_PORT_UNSET.env.meta.depends_on,
+        &["DEP_PORT_UNSET"]
+    );
+}
+
+#[test]
+fn depends_on_set() {
+    #[envflag(default = 8080)]
+    const DEP_PORT_SET: i64;
+
+    #[envflag(default_fn=metrics_port, depends_on=[DEP_PORT_SET])]
+    const DEP_METRICS_PORT_SET: i64;
+
+    fn metrics_port() -> anyhow::Result<i64> {
+        Ok(DEP_PORT_SET.fetch()? + 1)
+    }
+
+    std::env::set_var("DEP_PORT_SET", "80");
+    assert_eq!(DEP_METRICS_PORT_SET.fetch().unwrap(), 81);
+}
+
+#[test]
+fn depends_on_cycle() {
+    #[envflag(default_fn=ping_default, depends_on=[DEP_PONG])]
+    const DEP_PING: i64;
+
+    #[envflag(default_fn = pong_default)]
+    const DEP_PONG: i64;
+
+    fn ping_default() -> anyhow::Result<i64> {
+        DEP_PONG.fetch()
+    }
+
+    fn pong_default() -> anyhow::Result<i64> {
+        DEP_PING.fetch()
+    }
+
+    let err = DEP_PING.fetch().unwrap_err();
+    assert!(format!("{:#}", err).contains("DEP_PING -> DEP_PONG -> DEP_PING"));
+}
+
+#[test]
+fn depends_on_layer() {
+    #[envflag(default = 8080)]
+    const DEP_PORT_LAYER: i64;
+
+    #[envflag(default_fn=metrics_port, depends_on=[DEP_PORT_LAYER])]
+    const DEP_METRICS_PORT_LAYER: i64;
+
+    fn metrics_port() -> anyhow::Result<i64> {
+        Ok(DEP_PORT_LAYER.fetch()? + 1)
+    }
+
+    std::env::set_var("DEP_PORT_LAYER", "80");
+    let layer = rs_envflag::DotEnv::parse(".env", "DEP_PORT_LAYER=90").unwrap();
+    assert_eq!(DEP_METRICS_PORT_LAYER.fetch_from(&layer).unwrap(), 91);
+    let layer = rs_envflag::DotEnv::parse(".env", "").unwrap();
+    assert_eq!(DEP_METRICS_PORT_LAYER.fetch_from(&layer).unwrap(), 8081);
+    assert_eq!(DEP_METRICS_PORT_LAYER.fetch().unwrap(), 81);
+}
+
+#[test]
+fn depends_on_other_index() {
+    #[envflag(env_name = "DEP_REPLICA_{}_PORT", default_fn = replica_port)]
+    const DEP_REPLICA_PORT: i64;
+
+    fn replica_port() -> anyhow::Result<i64> {
+        Ok(DEP_REPLICA_PORT.fetch_for("primary")? + 1)
+    }
+
+    let layer = rs_envflag::DotEnv::parse(".env", "DEP_REPLICA_primary_PORT=5432").unwrap();
+    assert_eq!(DEP_REPLICA_PORT.fetch_for_from("1", &layer).unwrap(), 5433);
+
+    let layer = rs_envflag::DotEnv::parse(".env", "").unwrap();
+    let err = DEP_REPLICA_PORT.fetch_for_from("1", &layer).unwrap_err();
+    assert!(format!("{:#}", err).contains(
+        "Cyclic dependency among ENVs: DEP_REPLICA_primary_PORT -> DEP_REPLICA_primary_PORT"
+    ));
+}
+
+fn answer() -> anyhow::Result<i64> {
+    Ok(42)
+}
+
+struct W(String);
+
+impl W {
+    fn new() -> anyhow::Result<W> {
+        Ok(W("xixi".to_string()))
+    }
+}
+
+fn w_parser(_key: &str, value: &str) -> anyhow::Result<W> {
+    Ok(W(value.to_string()))
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct V(String);
 
@@ -216,3 +406,10 @@ impl V {
 fn v_parser(_key: &str, value: &str) -> anyhow::Result<V> {
     Ok(V(value.to_string()))
 }
+
+fn secs_parser(key: &str, value: &str) -> anyhow::Result<std::time::Duration> {
+    let Some(secs) = value.strip_suffix('s') else {
+        anyhow::bail!("Unrecognized ENV \"{}\": \"{}\"", key, value);
+    };
+    Ok(std::time::Duration::from_secs(secs.parse()?))
+}
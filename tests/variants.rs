@@ -0,0 +1,94 @@
+use rs_envflag::{variants_parser, Flag, Variants};
+use rs_envflag_macros::*;
+
+#[envflag(default_str = "control:50,treatment_a:25,treatment_b:25")]
+const VARIANTS_CHECKOUT: Variants;
+
+#[test]
+fn parse() {
+    let variants = variants_parser("K", "a:50, b : 30,c:20").unwrap();
+    assert_eq!(variants.names().collect::<Vec<_>>(), ["a", "b", "c"]);
+    assert_eq!(variants.weight("b"), Some(30));
+    assert_eq!(variants.weight("d"), None);
+    assert_eq!(variants.to_string(), "a:50,b:30,c:20");
+
+    for bad in [
+        "a:50,b:40",
+        "a:50,a:50",
+        "a:50,b",
+        ":100",
+        "a:-1,b:101",
+        "a:x",
+    ] {
+        assert!(variants_parser("K", bad).is_err(), "{}", bad);
+    }
+    assert_eq!(
+        variants_parser("K", "a:50,b:40").unwrap_err().to_string(),
+        "Unrecognized ENV \"K\": weights of variants sum to 90 but not 100"
+    );
+}
+
+#[test]
+fn variant_for() {
+    let variants = variants_parser("K", "a:50,b:30,c:20,d:0").unwrap();
+    let mut counts = [0; 4];
+    for id in 0..10000u64 {
+        let variant = variants.variant_for("EXPERIMENT", &id);
+        assert_eq!(variant, variants.variant_for("EXPERIMENT", &id));
+        counts[["a", "b", "c", "d"]
+            .iter()
+            .position(|x| *x == variant)
+            .unwrap()] += 1;
+    }
+    assert!((4500..5500).contains(&counts[0]));
+    assert!((2500..3500).contains(&counts[1]));
+    assert!((1500..2500).contains(&counts[2]));
+    assert_eq!(counts[3], 0);
+}
+
+#[test]
+fn flags() {
+    assert_eq!(
+        VARIANTS_CHECKOUT.meta().variants,
+        ["control", "treatment_a", "treatment_b"]
+    );
+    assert_eq!(VARIANTS_CHECKOUT.meta().type_name, "Variants");
+    let variant = VARIANTS_CHECKOUT.variant_for("user-1").unwrap();
+    assert!(["control", "treatment_a", "treatment_b"].contains(&variant.as_str()));
+
+    std::env::set_var("VARIANTS_CHECKOUT", "control:0,treatment_a:100");
+    assert_eq!(
+        VARIANTS_CHECKOUT.variant_for("user-1").unwrap(),
+        "treatment_a"
+    );
+    std::env::set_var("VARIANTS_CHECKOUT", "control:60,treatment_a:60");
+    assert!(VARIANTS_CHECKOUT.variant_for("user-1").is_err());
+}
+
+#[test]
+fn listed_variants() {
+    #[envflag(variants = ["control", "treatment"])]
+    const VARIANTS_SEARCH: Option<Variants>;
+
+    #[envflag(variants = ["control", "treatment"], default_fn = ranking)]
+    const VARIANTS_RANKING: Variants;
+
+    fn ranking() -> anyhow::Result<Variants> {
+        variants_parser("VARIANTS_RANKING", "control:100")
+    }
+
+    #[envflag(variants = ["control", "treatment_a", "treatment_b"], default_str = "control:100")]
+    const VARIANTS_LISTED: Variants;
+
+    assert_eq!(VARIANTS_SEARCH.meta().variants, ["control", "treatment"]);
+    assert_eq!(VARIANTS_RANKING.meta().variants, ["control", "treatment"]);
+    assert_eq!(
+        VARIANTS_LISTED.meta().variants,
+        ["control", "treatment_a", "treatment_b"]
+    );
+    assert_eq!(VARIANTS_SEARCH.fetch().unwrap(), None);
+    assert_eq!(
+        VARIANTS_RANKING.fetch().unwrap().weight("control"),
+        Some(100)
+    );
+}